@@ -1,13 +1,23 @@
-use std::sync::{Arc, Mutex};
+use std::{ops::Range, sync::Arc};
 
 use indicatif::ProgressBar;
 
 use crate::{
-    color::Color, hittables::prelude::*, material::material::ScatterResult, my_math::{prelude::*, ray},
+    color::Color,
+    hittables::prelude::*,
+    light::light::Light,
+    my_math::prelude::*,
+    output::image::Image,
+    render::{
+        adaptive::{AdaptiveSampling, PixelStats},
+        aov::{Aov, AovImages, AovPixel, AovSample},
+        integrator::{Integrator, PathTracer, RenderContext},
+        lights::LightList,
+        tile::{make_tiles, Tile, TileOrder},
+    },
 };
 
 use rayon::prelude::*;
-use std::sync::RwLock;
 
 struct CameraOrthonormalBasis {
     unit_vector_right: Vec3, // u
@@ -28,11 +38,37 @@ impl CameraOrthonormalBasis {
     }
 }
 
+// What a ray sees when it does not hit anything.
+#[derive(Debug, Clone, Copy)]
+pub enum Background {
+    // White at the horizon blending into light blue upwards.
+    Sky,
+    // A single color everywhere, black for scenes lit only by their own lights.
+    Solid(Color),
+}
+
+impl Background {
+    pub fn color(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Sky => {
+                let unit_direction = ray.direction.normalized();
+                let a = 0.5 * (unit_direction.y + 1.0);
+                Color::new(1.0, 1.0, 1.0) * (1.0 - a) + Color::new(0.5, 0.7, 1.0) * a
+            }
+            Background::Solid(color) => *color,
+        }
+    }
+}
+
 pub struct Camera {
     pub aspect_ratio: f64,
     pub image_width: u64,
     pub samples_per_pixel: u64,
     pub max_ray_bounces: u64,
+    // Number of bounces after which paths may be ended early by Russian roulette.
+    pub roulette_depth: u64,
+    // Image height in pixels, derived from `image_width` and `aspect_ratio` unless set.
+    pub fixed_image_height: Option<u64>,
     image_height: u64,
     pub look_from: Point3,
     pub look_at: Point3,
@@ -44,8 +80,50 @@ pub struct Camera {
     orthonormals: CameraOrthonormalBasis,
     pub defocus_angle: f64,
     pub focus_dist: f64,
+    // Camera rays are spread over the times from `shutter_open` to `shutter_close`, which
+    // blurs objects that move in between. Moving objects go from their start at time 0 to
+    // their end at time 1 and hold still outside of that, so a shutter of 0 to 1 shows the
+    // whole motion. The default shutter is instantaneous.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    pub background: Background,
+    // Point, spot and directional lights, which light the scene along with its emissive
+    // objects and the background.
+    pub lights: Vec<Arc<dyn Light>>,
     defocus_disk_u: Vec3,
     defocus_disk_v: Vec3,
+    // Progress bar and render info on stderr.
+    pub show_progress: bool,
+    // Every sample draws its random numbers from a stream derived from this seed and the
+    // sample's pixel and index, so a render is reproducible regardless of thread count.
+    pub seed: u64,
+    // The image is rendered in square tiles of this side, one render task per tile.
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
+    // Progressive rendering: the whole frame gets this many samples per pixel at a time,
+    // until `samples_per_pixel` is reached. Zero renders everything in a single pass.
+    pub samples_per_pass: u64,
+    // With adaptive sampling a pixel stops getting samples once it has converged, so
+    // `samples_per_pixel` becomes an upper bound.
+    pub adaptive: Option<AdaptiveSampling>,
+    // Computes the color seen along each camera ray.
+    pub integrator: Arc<dyn Integrator>,
+    // Auxiliary images to render along with the beauty image.
+    pub aovs: Vec<Aov>,
+}
+
+// The samples one pass took for a pixel.
+struct PixelSamples {
+    color_sum: Color,
+    count: u64,
+    aovs: AovPixel,
+}
+
+// Everything a render produces.
+pub struct RenderOutput {
+    pub image: Image,
+    // One image per requested AOV, in the order of `Camera::aovs`.
+    pub aovs: Vec<(Aov, Image)>,
 }
 
 impl Default for Camera {
@@ -57,6 +135,8 @@ impl Default for Camera {
             image_width: 100,
             samples_per_pixel: 10,
             max_ray_bounces: 10,
+            roulette_depth: 3,
+            fixed_image_height: None,
             image_height: 0,
             look_from: Point3::new(0., 0., -1.),
             look_at: vec_null,
@@ -68,67 +148,162 @@ impl Default for Camera {
             orthonormals: CameraOrthonormalBasis::new(&Vec3::new(0., 0., 1.), &vec_null, &vec_up),
             defocus_angle: 0.,
             focus_dist: 10.,
+            shutter_open: 0.,
+            shutter_close: 0.,
+            background: Background::Sky,
+            lights: Vec::new(),
             defocus_disk_u: vec_null,
             defocus_disk_v: vec_null,
+            show_progress: true,
+            seed: 0,
+            tile_size: 32,
+            tile_order: TileOrder::Scanline,
+            samples_per_pass: 0,
+            adaptive: None,
+            integrator: Arc::new(PathTracer),
+            aovs: Vec::new(),
         }
     }
 }
 
 impl Camera {
-    pub fn render(&mut self, world: &HittableList) {
+    pub fn render(&mut self, world: &impl Hittable) -> Image {
+        self.render_progressive(world, |_, _| {}).image
+    }
+
+    // Renders in passes of `samples_per_pass` samples and calls `on_pass` with the image
+    // accumulated so far and the number of samples per pixel taken up to now, after each
+    // pass including the last. Every sample keeps its own random stream, so the passes add
+    // up to the same image as a single-pass render.
+    pub fn render_progressive(
+        &mut self,
+        world: &impl Hittable,
+        mut on_pass: impl FnMut(&Image, u64),
+    ) -> RenderOutput {
         self.initialize();
-        println!("P3\n{} {}\n255", self.image_width, self.image_height);
-
-        let mem = Arc::new(RwLock::new(vec![
-            vec![
-                Color::new(0., 0., 0.);
-                self.image_width as usize
-            ];
-            self.image_height as usize
-        ]));
-
-        let bar = Arc::new(Mutex::new(ProgressBar::new(
-            self.image_width * self.image_height,
-        )));
-
-        let world = Arc::new(RwLock::new(world));
-        (0..self.image_height)
-            .flat_map(|y| (0..self.image_width).map(move |x| (x, y)))
-            .collect::<Vec<(u64, u64)>>()
-            .par_iter()
-            // .iter()
-            .for_each(|(x, y)| {
-                let (x, y) = (*x, *y);
-                let mut pixel_color = Color::new(0., 0., 0.);
 
-                // Access the world
-                let world = Arc::clone(&world);
-                let world = world.read().unwrap();
+        let bar = if self.show_progress {
+            ProgressBar::new(self.image_width * self.image_height * self.samples_per_pixel)
+        } else {
+            ProgressBar::hidden()
+        };
+
+        let mut image = Image::new(self.image_width as usize, self.image_height as usize);
+        let mut aov_images = AovImages::new(&self.aovs, image.width(), image.height());
+        let tiles = make_tiles(
+            image.width(),
+            image.height(),
+            self.tile_size,
+            self.tile_order,
+        );
+        let lights = LightList::new(world, &self.lights);
+        let context = RenderContext {
+            world,
+            lights: &lights,
+            background: self.background,
+            max_bounces: self.max_ray_bounces,
+            roulette_depth: self.roulette_depth,
+        };
+        let samples_per_pass = match self.samples_per_pass {
+            0 => self.samples_per_pixel,
+            samples => samples.min(self.samples_per_pixel),
+        };
 
-                // Sample the color
-                for _ in 0..self.samples_per_pixel {
-                    pixel_color += self.ray_color(self.get_ray(x, y), *world, self.max_ray_bounces);
-                }
+        // Per-pixel statistics for adaptive sampling, kept across passes, one list per tile.
+        let mut stats: Vec<Vec<PixelStats>> = tiles
+            .iter()
+            .map(|tile| vec![PixelStats::default(); tile.pixel_count()])
+            .collect();
 
-                // Save the color to the memory
-                let mem = Arc::clone(&mem);
-                mem.write().unwrap()[y as usize][x as usize] = pixel_color;
+        let mut first_sample = 0;
+        while first_sample < self.samples_per_pixel {
+            let samples =
+                first_sample..(first_sample + samples_per_pass).min(self.samples_per_pixel);
 
-                // Progress the bar
-                let bar = Arc::clone(&bar);
-                bar.lock().unwrap().inc(1);
-            });
+            // `par_bridge` hands the tiles out in order. Every tile is rendered into its own
+            // buffer and merged into the image once all of them are done. All the threads
+            // share the scene through `context`, which is why hittables, materials,
+            // textures, lights and integrators have to be `Send + Sync`.
+            let rendered: Vec<(Tile, Vec<PixelSamples>)> = tiles
+                .iter()
+                .zip(stats.iter_mut())
+                .par_bridge()
+                .map(|(tile, stats)| {
+                    let buffer = self.render_tile(tile, samples.clone(), stats, &context);
+                    bar.inc(tile.pixel_count() as u64 * (samples.end - samples.start));
+                    (*tile, buffer)
+                })
+                .collect();
 
-        mem.read().unwrap().iter().for_each(|row| {
-            row.iter()
-                .for_each(|c| c.write_color(self.samples_per_pixel))
-        });
+            for (tile, buffer) in rendered {
+                for ((x, y), pixel) in tile.pixels().zip(buffer) {
+                    image.add_samples(x, y, pixel.color_sum, pixel.count);
+                    aov_images.add_samples(x, y, &pixel.aovs, pixel.count);
+                }
+            }
+            on_pass(&image, samples.end);
+            first_sample = samples.end;
+        }
+        bar.finish();
+        RenderOutput {
+            image,
+            aovs: aov_images.finish(),
+        }
+    }
+
+    // Sums and counts of the given samples of the tile's pixels, row by row, with their AOVs
+    // if any were requested. Pixels that have converged according to `self.adaptive` get
+    // fewer samples, or none at all.
+    fn render_tile(
+        &self,
+        tile: &Tile,
+        samples: Range<u64>,
+        stats: &mut [PixelStats],
+        context: &RenderContext,
+    ) -> Vec<PixelSamples> {
+        let with_aovs = !self.aovs.is_empty();
+        tile.pixels()
+            .zip(stats)
+            .map(|((x, y), stats)| {
+                let (x, y) = (x as u64, y as u64);
+                let mut pixel_color = Color::new(0., 0., 0.);
+                let mut count = 0;
+                let mut aov_pixel = AovPixel::default();
+                for sample in samples.clone() {
+                    if self
+                        .adaptive
+                        .is_some_and(|adaptive| adaptive.is_converged(stats))
+                    {
+                        break;
+                    }
+                    seed_thread_rng(derive_seed(self.seed, &[x, y, sample]));
+                    let ray = self.get_ray(x, y);
+                    let color = self.integrator.radiance(ray, context);
+                    // Traced after the integrator, which thus sees the same random numbers
+                    // with or without AOVs.
+                    if with_aovs {
+                        aov_pixel.add(&AovSample::trace(&ray, context));
+                    }
+                    stats.add(color);
+                    pixel_color += color;
+                    count += 1;
+                }
+                PixelSamples {
+                    color_sum: pixel_color,
+                    count,
+                    aovs: aov_pixel,
+                }
+            })
+            .collect()
     }
 
     fn initialize(&mut self) {
         self.orthonormals =
             CameraOrthonormalBasis::new(&self.look_from, &self.look_at, &self.up_direction);
-        self.image_height = (self.image_width as f64 / self.aspect_ratio) as u64;
+        self.image_height = match self.fixed_image_height {
+            Some(height) => height,
+            None => (self.image_width as f64 / self.aspect_ratio).round() as u64,
+        };
         if self.image_height < 1 {
             self.image_height = 1;
         }
@@ -157,33 +332,11 @@ impl Camera {
         self.defocus_disk_u = self.orthonormals.unit_vector_right * defocus_radius;
         self.defocus_disk_v = self.orthonormals.camera_up * defocus_radius;
 
-        eprintln!(
-            "Camera image: {} x {} with viewport: {} x {}...",
-            self.image_width, self.image_height, viewport_width, viewport_height
-        );
-    }
-
-    fn ray_color(&self, ray: Ray, world: &impl Hittable, bounces_left: u64) -> Color {
-        let hit = if bounces_left == 0 {
-            HitResult::Miss
-        } else {
-            world.hit(&ray, &Interval::new(0.01, INFINITY))
-        };
-
-        match hit {
-            HitResult::Hit(hit_record) => match hit_record.material.scatter(&ray, &hit_record) {
-                ScatterResult::Scatter { ray, attenuation } => {
-                    let color: Color = attenuation;
-                    let ray: Vec3 = self.ray_color(ray, world, bounces_left - 1);
-                    ray * color
-                }
-                ScatterResult::Consume => Color::new(0., 0., 0.),
-            },
-            HitResult::Miss => {
-                let unit_direction = ray.direction.normalized();
-                let a = 0.5 * (unit_direction.y + 1.0);
-                Color::new(1.0, 1.0, 1.0) * (1.0 - a) + Color::new(0.5, 0.7, 1.0) * a
-            }
+        if self.show_progress {
+            eprintln!(
+                "Camera image: {} x {} with viewport: {} x {}...",
+                self.image_width, self.image_height, viewport_width, viewport_height
+            );
         }
     }
 
@@ -197,7 +350,17 @@ impl Camera {
         };
         let pixel_sample = pixel_center + self.pixel_sample_square();
         let ray_direction = pixel_sample - ray_origin;
-        Ray::new(ray_origin, ray_direction)
+        Ray::new(ray_origin, ray_direction, self.sample_time())
+    }
+
+    fn sample_time(&self) -> f64 {
+        // No random number is drawn for an instantaneous shutter, which leaves renders of
+        // still scenes as they were.
+        if self.shutter_close > self.shutter_open {
+            self.shutter_open + random_double() * (self.shutter_close - self.shutter_open)
+        } else {
+            self.shutter_open
+        }
     }
 
     fn pixel_sample_square(&self) -> Vec3 {
@@ -5,22 +5,26 @@ pub type Color = Vec3;
 const INTENSITY: Interval = Interval::new(0., 0.999);
 
 impl Color {
-    pub fn write_color(&self, num_samples: u64) {
-        let num_samples = num_samples as f64;
-        let clr = *self / num_samples;
-
+    pub fn to_rgb8(self) -> [u8; 3] {
         // Most image viewers assume an image is in gamma space, not in linear space,
         // so we have to convert it to gamma space (gamma 2 to be more specific).
-        let clr = clr.to_gamma();
-
-        println!(
-            "{} {} {}",
-            (256. * INTENSITY.clamp(clr.x)) as u64,
-            (256. * INTENSITY.clamp(clr.y)) as u64,
-            (256. * INTENSITY.clamp(clr.z)) as u64
-        );
+        let clr = self.to_gamma();
+        [
+            (256. * INTENSITY.clamp(clr.x)) as u8,
+            (256. * INTENSITY.clamp(clr.y)) as u8,
+            (256. * INTENSITY.clamp(clr.z)) as u8,
+        ]
+    }
+    pub fn to_rgb16(self) -> [u16; 3] {
+        let clr = self.to_gamma();
+        let channel = |c: f64| (65535. * c.clamp(0., 1.)).round() as u16;
+        [channel(clr.x), channel(clr.y), channel(clr.z)]
+    }
+    // Perceived brightness of a linear color (Rec. 709 weights).
+    pub fn luminance(self) -> f64 {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
     }
-    pub fn to_gamma(&self) -> Self {
+    pub fn to_gamma(self) -> Self {
         Color {
             x: self.x.sqrt(),
             y: self.y.sqrt(),
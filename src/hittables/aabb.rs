@@ -0,0 +1,117 @@
+use crate::my_math::prelude::*;
+
+// Axis-aligned bounding box, stored as one interval per axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    // Boxes thinner than this get padded, so that flat primitives still have a volume
+    // that the slab test below can hit.
+    const MIN_THICKNESS: f64 = 1e-4;
+
+    pub const EMPTY: Aabb = Aabb::from_intervals(Interval::EMPTY, Interval::EMPTY, Interval::EMPTY);
+    pub const UNIVERSE: Aabb =
+        Aabb::from_intervals(Interval::UNIVERSE, Interval::UNIVERSE, Interval::UNIVERSE);
+
+    pub const fn from_intervals(x: Interval, y: Interval, z: Interval) -> Self {
+        Aabb { x, y, z }
+    }
+
+    pub fn new(a: Point3, b: Point3) -> Self {
+        // The points are treated as two opposite corners, in any order.
+        let mut bbox = Aabb {
+            x: Interval::new(a.x.min(b.x), a.x.max(b.x)),
+            y: Interval::new(a.y.min(b.y), a.y.max(b.y)),
+            z: Interval::new(a.z.min(b.z), a.z.max(b.z)),
+        };
+        bbox.pad_to_minimums();
+        bbox
+    }
+
+    pub fn surrounding(a: &Aabb, b: &Aabb) -> Self {
+        Aabb {
+            x: Interval::enclosing(&a.x, &b.x),
+            y: Interval::enclosing(&a.y, &b.y),
+            z: Interval::enclosing(&a.z, &b.z),
+        }
+    }
+
+    pub fn axis_interval(&self, axis: usize) -> &Interval {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            _ => &self.z,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.min > self.x.max || self.y.min > self.y.max || self.z.min > self.z.max
+    }
+
+    // Whether the box is finite. Empty boxes count as bounded; infinite planes are not.
+    pub fn is_bounded(&self) -> bool {
+        self.is_empty()
+            || [self.x, self.y, self.z]
+                .iter()
+                .all(|interval| interval.min.is_finite() && interval.max.is_finite())
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::new(
+            (self.x.min + self.x.max) * 0.5,
+            (self.y.min + self.y.max) * 0.5,
+            (self.z.min + self.z.max) * 0.5,
+        )
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            return 0.;
+        }
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        2. * (dx * dy + dy * dz + dz * dx)
+    }
+
+    pub fn hit(&self, ray: &Ray, ray_t: &Interval) -> bool {
+        // Slab test: clip the ray interval against each pair of axis-aligned planes,
+        // the box is hit if anything is left of the interval at the end.
+        let mut t_min = ray_t.min;
+        let mut t_max = ray_t.max;
+
+        for axis in 0..3 {
+            let ax = self.axis_interval(axis);
+            let inv_direction = 1. / ray.direction[axis];
+
+            let t0 = (ax.min - ray.origin[axis]) * inv_direction;
+            let t1 = (ax.max - ray.origin[axis]) * inv_direction;
+
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            if t0 > t_min {
+                t_min = t0;
+            }
+            if t1 < t_max {
+                t_max = t1;
+            }
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn pad_to_minimums(&mut self) {
+        if self.x.size() < Self::MIN_THICKNESS {
+            self.x = self.x.expand(Self::MIN_THICKNESS);
+        }
+        if self.y.size() < Self::MIN_THICKNESS {
+            self.y = self.y.expand(Self::MIN_THICKNESS);
+        }
+        if self.z.size() < Self::MIN_THICKNESS {
+            self.z = self.z.expand(Self::MIN_THICKNESS);
+        }
+    }
+}
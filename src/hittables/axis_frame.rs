@@ -0,0 +1,93 @@
+use super::hittable::HitRecord;
+use crate::{material::material::Material, my_math::prelude::*};
+use std::sync::Arc;
+
+// Local coordinates for shapes built around an axis, such as cylinders and tori: the
+// origin at `origin` and the z axis along the shape's axis. Intersections are computed
+// on the ray moved into this frame, where the shape has a simple equation.
+pub struct AxisFrame {
+    pub origin: Point3,
+    pub axes: Onb,
+}
+
+// A hit found in local coordinates.
+pub struct LocalHit {
+    pub t: f64,
+    // Outward unit normal, in local coordinates.
+    pub normal: Vec3,
+    pub u: f64,
+    pub v: f64,
+}
+
+impl AxisFrame {
+    pub fn new(origin: Point3, axis: &Vec3) -> Self {
+        AxisFrame {
+            origin,
+            axes: Onb::new(axis),
+        }
+    }
+
+    // The ray in local coordinates. The basis is orthonormal, so the ray parameter `t`
+    // means the same in both frames.
+    pub fn ray_to_local(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            self.axes.coordinates(&(ray.origin - self.origin)),
+            self.axes.coordinates(&ray.direction),
+            ray.time,
+        )
+    }
+
+    pub fn hit_record(&self, ray: &Ray, hit: &LocalHit, material: &Arc<dyn Material>) -> HitRecord {
+        let mut rec = HitRecord::empty();
+        rec.t = hit.t;
+        rec.intersection_point = ray.at(hit.t);
+        rec.set_face_normal(ray, &self.axes.local(&hit.normal));
+        (rec.u, rec.v) = (hit.u, hit.v);
+        rec.material = Arc::clone(material);
+        rec
+    }
+}
+
+// Replaces `closest` with `candidate` if the candidate is inside `ray_t` and nearer.
+pub fn keep_closest(closest: &mut Option<LocalHit>, ray_t: &Interval, candidate: LocalHit) {
+    if ray_t.surrounds(candidate.t) && closest.as_ref().is_none_or(|hit| candidate.t < hit.t) {
+        *closest = Some(candidate);
+    }
+}
+
+// Both roots of a t^2 + 2 half_b t + c = 0, smallest first, if there are any.
+pub fn quadratic_roots(a: f64, half_b: f64, c: f64) -> Option<(f64, f64)> {
+    let discriminant = half_b * half_b - a * c;
+    if a == 0. || discriminant < 0. {
+        return None;
+    }
+    let sqrt_discriminant = discriminant.sqrt();
+    let (t0, t1) = (
+        (-half_b - sqrt_discriminant) / a,
+        (-half_b + sqrt_discriminant) / a,
+    );
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+// Angle of a local point around the z axis, as a texture coordinate in [0, 1).
+pub fn azimuth(point: &Point3) -> f64 {
+    (point.y.atan2(point.x) / (2. * PI)).rem_euclid(1.)
+}
+
+// Where a local ray crosses the disk of the given radius around the z axis at height `z`,
+// facing along z if `normal_z` is 1 or against it if -1. The disk is textured with u
+// around the axis and v from the center out to the rim.
+pub fn cap_hit(local_ray: &Ray, z: f64, radius: f64, normal_z: f64) -> Option<LocalHit> {
+    if local_ray.direction.z == 0. {
+        return None;
+    }
+    let t = (z - local_ray.origin.z) / local_ray.direction.z;
+    let point = local_ray.at(t);
+    let distance_squared = point.x * point.x + point.y * point.y;
+    (distance_squared <= radius * radius).then(|| LocalHit {
+        t,
+        normal: Vec3::new(0., 0., normal_z),
+        u: azimuth(&point),
+        v: distance_squared.sqrt() / radius,
+    })
+}
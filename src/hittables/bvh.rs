@@ -0,0 +1,287 @@
+use crate::my_math::prelude::*;
+
+use super::{
+    aabb::Aabb,
+    hittable::{HitResult, Hittable},
+    hittable_list::HittableList,
+};
+use std::sync::Arc;
+
+// Relative costs used by the surface area heuristic. Only their ratio matters.
+const TRAVERSAL_COST: f64 = 0.125;
+const INTERSECTION_COST: f64 = 1.0;
+
+// Number of centroid buckets evaluated per axis when looking for a split.
+const SAH_BUCKETS: usize = 16;
+
+// Nodes with at most this many objects may become leaves if splitting does not pay off.
+const MAX_LEAF_SIZE: usize = 4;
+
+struct BvhPrimitive {
+    // Position in the list the BVH was built from, reported as the hit's `object_id`.
+    index: usize,
+    object: Arc<dyn Hittable>,
+    bbox: Aabb,
+    centroid: Point3,
+}
+
+enum BvhChildren {
+    Leaf(Vec<(usize, Arc<dyn Hittable>)>),
+    Split {
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+        axis: usize,
+    },
+}
+
+// Bounding volume hierarchy over a set of hittables, built with a binned
+// surface area heuristic. Use it in place of a `HittableList` for big scenes.
+pub struct BvhNode {
+    bbox: Aabb,
+    children: BvhChildren,
+}
+
+#[derive(Clone, Copy)]
+struct SahBucket {
+    count: usize,
+    bbox: Aabb,
+}
+
+impl SahBucket {
+    const EMPTY: SahBucket = SahBucket {
+        count: 0,
+        bbox: Aabb::EMPTY,
+    };
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList) -> Self {
+        BvhNode::from_objects(list.into_objects())
+    }
+
+    pub fn from_objects(objects: Vec<Arc<dyn Hittable>>) -> Self {
+        let primitives: Vec<BvhPrimitive> = objects
+            .into_iter()
+            .enumerate()
+            .map(|(index, object)| {
+                let bbox = object.bounding_box();
+                BvhPrimitive {
+                    index,
+                    object,
+                    bbox,
+                    centroid: bbox.centroid(),
+                }
+            })
+            .collect();
+
+        // Unbounded objects such as planes have no centroid to split on and would make
+        // every box infinite, so they go into a leaf of their own next to the tree.
+        let (unbounded, mut bounded): (Vec<_>, Vec<_>) =
+            primitives.into_iter().partition(|p| !p.bbox.is_bounded());
+        let tree = BvhNode::build(&mut bounded);
+        if unbounded.is_empty() {
+            return tree;
+        }
+        let leaf = BvhNode::make_leaf(&unbounded, Aabb::UNIVERSE);
+        BvhNode {
+            bbox: Aabb::UNIVERSE,
+            children: BvhChildren::Split {
+                left: Box::new(leaf),
+                right: Box::new(tree),
+                axis: 0,
+            },
+        }
+    }
+
+    fn make_leaf(primitives: &[BvhPrimitive], bbox: Aabb) -> Self {
+        BvhNode {
+            bbox,
+            children: BvhChildren::Leaf(
+                primitives
+                    .iter()
+                    .map(|p| (p.index, Arc::clone(&p.object)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn build(primitives: &mut [BvhPrimitive]) -> Self {
+        let bbox = primitives
+            .iter()
+            .fold(Aabb::EMPTY, |acc, p| Aabb::surrounding(&acc, &p.bbox));
+
+        if primitives.len() <= 1 {
+            return BvhNode::make_leaf(primitives, bbox);
+        }
+
+        // Splits are decided on the centroids rather than on the full boxes, so that
+        // every primitive falls into exactly one bucket.
+        let centroid_bounds = primitives.iter().fold(Aabb::EMPTY, |acc, p| {
+            Aabb::surrounding(
+                &acc,
+                &Aabb::from_intervals(
+                    Interval::new(p.centroid.x, p.centroid.x),
+                    Interval::new(p.centroid.y, p.centroid.y),
+                    Interval::new(p.centroid.z, p.centroid.z),
+                ),
+            )
+        });
+
+        let mut best: Option<(usize, usize, f64)> = None; // (axis, split bucket, cost)
+        for axis in 0..3 {
+            let extent = centroid_bounds.axis_interval(axis);
+            if extent.size() <= 0. {
+                continue;
+            }
+
+            let mut buckets = [SahBucket::EMPTY; SAH_BUCKETS];
+            for p in primitives.iter() {
+                let b = &mut buckets[Self::bucket_index(p.centroid[axis], extent)];
+                b.count += 1;
+                b.bbox = Aabb::surrounding(&b.bbox, &p.bbox);
+            }
+
+            // Sweep from the right to get the cost of every right-hand side, then from
+            // the left to combine it with every left-hand side.
+            let mut right_area = [0.; SAH_BUCKETS];
+            let mut right_count = [0; SAH_BUCKETS];
+            let mut acc = SahBucket::EMPTY;
+            for i in (1..SAH_BUCKETS).rev() {
+                acc.count += buckets[i].count;
+                acc.bbox = Aabb::surrounding(&acc.bbox, &buckets[i].bbox);
+                right_area[i] = acc.bbox.surface_area();
+                right_count[i] = acc.count;
+            }
+
+            let mut acc = SahBucket::EMPTY;
+            for split in 1..SAH_BUCKETS {
+                acc.count += buckets[split - 1].count;
+                acc.bbox = Aabb::surrounding(&acc.bbox, &buckets[split - 1].bbox);
+                if acc.count == 0 || right_count[split] == 0 {
+                    continue;
+                }
+                let cost = TRAVERSAL_COST
+                    + INTERSECTION_COST
+                        * (acc.bbox.surface_area() * acc.count as f64
+                            + right_area[split] * right_count[split] as f64)
+                        / bbox.surface_area();
+                if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                    best = Some((axis, split, cost));
+                }
+            }
+        }
+
+        let leaf_cost = INTERSECTION_COST * primitives.len() as f64;
+        let (axis, mid) = match best {
+            Some((_, _, cost)) if primitives.len() <= MAX_LEAF_SIZE && cost >= leaf_cost => {
+                return BvhNode::make_leaf(primitives, bbox);
+            }
+            Some((axis, split, _)) => {
+                let extent = *centroid_bounds.axis_interval(axis);
+                let mid = Self::partition(primitives, |p| {
+                    Self::bucket_index(p.centroid[axis], &extent) < split
+                });
+                (axis, mid)
+            }
+            None if primitives.len() <= MAX_LEAF_SIZE => {
+                return BvhNode::make_leaf(primitives, bbox);
+            }
+            // All centroids coincide, so no bucket split exists. Split by count instead.
+            None => (0, primitives.len() / 2),
+        };
+
+        let (left, right) = primitives.split_at_mut(mid);
+        BvhNode {
+            bbox,
+            children: BvhChildren::Split {
+                left: Box::new(BvhNode::build(left)),
+                right: Box::new(BvhNode::build(right)),
+                axis,
+            },
+        }
+    }
+
+    fn bucket_index(value: f64, extent: &Interval) -> usize {
+        let b = ((value - extent.min) / extent.size() * SAH_BUCKETS as f64) as usize;
+        b.min(SAH_BUCKETS - 1)
+    }
+
+    fn partition(
+        primitives: &mut [BvhPrimitive],
+        goes_left: impl Fn(&BvhPrimitive) -> bool,
+    ) -> usize {
+        let mut mid = 0;
+        for i in 0..primitives.len() {
+            if goes_left(&primitives[i]) {
+                primitives.swap(i, mid);
+                mid += 1;
+            }
+        }
+        mid
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        if !self.bbox.hit(ray, ray_t) {
+            return HitResult::Miss;
+        }
+
+        match &self.children {
+            BvhChildren::Leaf(objects) => {
+                let mut closest_so_far = ray_t.max;
+                let mut hit_result = HitResult::Miss;
+                for (object_id, obj) in objects.iter() {
+                    if let HitResult::Hit(mut x) =
+                        obj.hit(ray, &Interval::new(ray_t.min, closest_so_far))
+                    {
+                        closest_so_far = x.t;
+                        x.object_id = *object_id;
+                        hit_result = HitResult::Hit(x);
+                    }
+                }
+                hit_result
+            }
+            BvhChildren::Split { left, right, axis } => {
+                // Visit the child closer to the ray origin first, so the second one
+                // can often be skipped thanks to the shortened interval.
+                let (near, far) = if ray.direction[*axis] < 0. {
+                    (right, left)
+                } else {
+                    (left, right)
+                };
+                let near_hit = near.hit(ray, ray_t);
+                let closest_so_far = match &near_hit {
+                    HitResult::Hit(rec) => rec.t,
+                    HitResult::Miss => ray_t.max,
+                };
+                match far.hit(ray, &Interval::new(ray_t.min, closest_so_far)) {
+                    HitResult::Miss => near_hit,
+                    far_hit => far_hit,
+                }
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn collect_emitters(&self, emitters: &mut Vec<Arc<dyn Hittable>>) {
+        match &self.children {
+            BvhChildren::Leaf(objects) => {
+                for (_, object) in objects {
+                    if object.is_emitter() {
+                        emitters.push(Arc::clone(object));
+                    } else {
+                        object.collect_emitters(emitters);
+                    }
+                }
+            }
+            BvhChildren::Split { left, right, .. } => {
+                left.collect_emitters(emitters);
+                right.collect_emitters(emitters);
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use super::{
+    aabb::Aabb,
+    axis_frame::{azimuth, keep_closest, quadratic_roots, AxisFrame, LocalHit},
+    hittable::{HitResult, Hittable},
+};
+use crate::{material::material::Material, my_math::prelude::*};
+use std::sync::Arc;
+
+// All the points within `radius` of the segment from `start` to `end`: a cylinder with
+// hemispheres on both ends. It is textured with u around the axis and v along the whole
+// length, from the tip of the `start` hemisphere to the tip of the `end` one.
+pub struct Capsule {
+    frame: AxisFrame,
+    length: f64,
+    radius: f64,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Capsule {
+    pub fn new(start: Point3, end: Point3, radius: f64, material: Arc<dyn Material>) -> Self {
+        let axis = end - start;
+        // Without a length the capsule is a sphere, and any axis will do.
+        let axis = if axis.near_zero() {
+            Vec3::new(0., 1., 0.)
+        } else {
+            axis
+        };
+        let radius_vec = Vec3::new(radius, radius, radius);
+        Capsule {
+            frame: AxisFrame::new(start, &axis),
+            length: (end - start).length(),
+            radius,
+            material,
+            bbox: Aabb::surrounding(
+                &Aabb::new(start - radius_vec, start + radius_vec),
+                &Aabb::new(end - radius_vec, end + radius_vec),
+            ),
+        }
+    }
+}
+
+impl Hittable for Capsule {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        let local_ray = self.frame.ray_to_local(ray);
+        let (o, d) = (local_ray.origin, local_ray.direction);
+        let radius_squared = self.radius * self.radius;
+
+        // Candidates from the infinite cylinder around the axis and from the spheres around
+        // both ends, each only counting on its own part of the capsule.
+        let mut roots = Vec::with_capacity(6);
+        let a = d.x * d.x + d.y * d.y;
+        let half_b = o.x * d.x + o.y * d.y;
+        let c = o.x * o.x + o.y * o.y - radius_squared;
+        if let Some((t0, t1)) = quadratic_roots(a, half_b, c) {
+            roots.extend([t0, t1].into_iter().filter(|&t| {
+                let z = local_ray.at(t).z;
+                (0. ..=self.length).contains(&z)
+            }));
+        }
+        for end_z in [0., self.length] {
+            let oc = o - Vec3::new(0., 0., end_z);
+            let sphere_roots = quadratic_roots(
+                d.length_squared(),
+                d.dot(&oc),
+                oc.length_squared() - radius_squared,
+            );
+            if let Some((t0, t1)) = sphere_roots {
+                roots.extend([t0, t1].into_iter().filter(|&t| {
+                    let z = local_ray.at(t).z;
+                    if end_z == 0. {
+                        z <= 0.
+                    } else {
+                        z >= self.length
+                    }
+                }));
+            }
+        }
+
+        let mut closest = None;
+        for t in roots {
+            let point = local_ray.at(t);
+            let nearest_on_axis = Vec3::new(0., 0., point.z.clamp(0., self.length));
+            let candidate = LocalHit {
+                t,
+                normal: (point - nearest_on_axis) / self.radius,
+                u: azimuth(&point),
+                v: ((point.z + self.radius) / (self.length + 2. * self.radius)).clamp(0., 1.),
+            };
+            keep_closest(&mut closest, ray_t, candidate);
+        }
+
+        match closest {
+            Some(hit) => HitResult::Hit(self.frame.hit_record(ray, &hit, &self.material)),
+            None => HitResult::Miss,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -0,0 +1,95 @@
+use super::{
+    aabb::Aabb,
+    hittable::{HitRecord, HitResult, Hittable},
+    sphere::Sphere,
+};
+use crate::{material::material::Material, my_math::prelude::*};
+use std::sync::Arc;
+
+// A participating medium of constant density, such as smoke or fog, filling the inside of
+// a closed `boundary`. A ray going through it scatters at a random distance, sooner the
+// denser the medium is, or passes through unchanged. Where it scatters, the phase function
+// picks the new direction.
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable>,
+    // Chance of scattering per unit of distance.
+    density: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Arc<dyn Hittable>,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        ConstantMedium {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+
+    // Fog filling the ball of `radius` around `center`. Made large enough to hold the
+    // whole scene, it acts as global fog, with the background still visible beyond it.
+    pub fn fog(
+        center: Point3,
+        radius: f64,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        let boundary = Sphere::new(center, radius, Arc::clone(&phase_function));
+        ConstantMedium::new(Arc::new(boundary), density, phase_function)
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        // The distance travelled in the medium before scattering is exponentially
+        // distributed. Having no memory, a single draw holds for all the stretches the ray
+        // spends inside the boundary.
+        let speed = ray.direction.length();
+        let mut remaining = -random_double().ln() / self.density;
+
+        let mut t = ray_t.min;
+        while t < ray_t.max {
+            let HitResult::Hit(crossing) = self.boundary.hit(ray, &Interval::new(t, INFINITY))
+            else {
+                return HitResult::Miss;
+            };
+            // The ray either enters the medium and stays until it crosses the boundary
+            // again, or was inside from `t` on and is now leaving.
+            let (start, end) = if crossing.front_face {
+                match self.boundary.hit(ray, &Interval::new(crossing.t, INFINITY)) {
+                    HitResult::Hit(exit) => (crossing.t, exit.t),
+                    HitResult::Miss => return HitResult::Miss,
+                }
+            } else {
+                (t, crossing.t)
+            };
+            if start >= ray_t.max {
+                return HitResult::Miss;
+            }
+
+            let length = (end.min(ray_t.max) - start) * speed;
+            if remaining < length {
+                let mut rec = HitRecord::empty();
+                rec.t = start + remaining / speed;
+                rec.intersection_point = ray.at(rec.t);
+                // There is no surface. The normal faces back along the ray, for the sake
+                // of code that wants one.
+                rec.normal = -ray.direction / speed;
+                rec.front_face = true;
+                rec.material = Arc::clone(&self.phase_function);
+                return HitResult::Hit(rec);
+            }
+            remaining -= length;
+            t = end;
+        }
+        HitResult::Miss
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
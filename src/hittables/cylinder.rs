@@ -0,0 +1,199 @@
+use super::{
+    aabb::Aabb,
+    axis_frame::{azimuth, cap_hit, keep_closest, quadratic_roots, AxisFrame, LocalHit},
+    disk::disk_extent,
+    hittable::{HitResult, Hittable},
+};
+use crate::{material::material::Material, my_math::prelude::*};
+use std::sync::Arc;
+
+// Circular cylinder between the centers of its two ends, closed by flat caps or open.
+// The side is textured with u around the axis and v from `base` to `top`.
+pub struct Cylinder {
+    frame: AxisFrame,
+    height: f64,
+    radius: f64,
+    capped: bool,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Point3,
+        top: Point3,
+        radius: f64,
+        capped: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let frame = AxisFrame::new(base, &(top - base));
+        let extent = disk_extent(&frame.axes.w, radius);
+        Cylinder {
+            frame,
+            height: (top - base).length(),
+            radius,
+            capped,
+            material,
+            bbox: Aabb::surrounding(
+                &Aabb::new(base - extent, base + extent),
+                &Aabb::new(top - extent, top + extent),
+            ),
+        }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        let local_ray = self.frame.ray_to_local(ray);
+        let (o, d) = (local_ray.origin, local_ray.direction);
+        let mut closest = None;
+
+        // The side: x^2 + y^2 = radius^2 between the two ends.
+        let a = d.x * d.x + d.y * d.y;
+        let half_b = o.x * d.x + o.y * d.y;
+        let c = o.x * o.x + o.y * o.y - self.radius * self.radius;
+        if let Some((t0, t1)) = quadratic_roots(a, half_b, c) {
+            for t in [t0, t1] {
+                let point = local_ray.at(t);
+                if (0. ..=self.height).contains(&point.z) {
+                    let candidate = LocalHit {
+                        t,
+                        normal: Vec3::new(point.x, point.y, 0.) / self.radius,
+                        u: azimuth(&point),
+                        v: point.z / self.height,
+                    };
+                    keep_closest(&mut closest, ray_t, candidate);
+                }
+            }
+        }
+
+        if self.capped {
+            let caps = [
+                cap_hit(&local_ray, 0., self.radius, -1.),
+                cap_hit(&local_ray, self.height, self.radius, 1.),
+            ];
+            for candidate in caps.into_iter().flatten() {
+                keep_closest(&mut closest, ray_t, candidate);
+            }
+        }
+
+        match closest {
+            Some(hit) => HitResult::Hit(self.frame.hit_record(ray, &hit, &self.material)),
+            None => HitResult::Miss,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+// Cone, or truncated cone, between two circles around a common axis: `base_radius`
+// around `base` and `top_radius` around `top`. A top radius of zero makes `top` the apex.
+// Capped cones are closed by flat disks at the ends that have a radius. Textured like
+// `Cylinder`.
+pub struct Cone {
+    frame: AxisFrame,
+    height: f64,
+    base_radius: f64,
+    top_radius: f64,
+    capped: bool,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point3,
+        top: Point3,
+        base_radius: f64,
+        top_radius: f64,
+        capped: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let frame = AxisFrame::new(base, &(top - base));
+        let base_extent = disk_extent(&frame.axes.w, base_radius);
+        let top_extent = disk_extent(&frame.axes.w, top_radius);
+        Cone {
+            frame,
+            height: (top - base).length(),
+            base_radius,
+            top_radius,
+            capped,
+            material,
+            bbox: Aabb::surrounding(
+                &Aabb::new(base - base_extent, base + base_extent),
+                &Aabb::new(top - top_extent, top + top_extent),
+            ),
+        }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        let local_ray = self.frame.ray_to_local(ray);
+        let (o, d) = (local_ray.origin, local_ray.direction);
+        let mut closest = None;
+
+        // The side: x^2 + y^2 = radius(z)^2, where the radius changes linearly with z by
+        // `slope`. Substituting the ray gives a quadratic in t again.
+        let slope = (self.top_radius - self.base_radius) / self.height;
+        let radius_at_origin = self.base_radius + slope * o.z;
+        let a = d.x * d.x + d.y * d.y - slope * slope * d.z * d.z;
+        let half_b = o.x * d.x + o.y * d.y - slope * d.z * radius_at_origin;
+        let c = o.x * o.x + o.y * o.y - radius_at_origin * radius_at_origin;
+        let roots = if a.abs() < 1e-12 {
+            // The ray is parallel to the side, so it crosses the surface only once.
+            (half_b != 0.).then(|| {
+                let t = -c / (2. * half_b);
+                (t, t)
+            })
+        } else {
+            quadratic_roots(a, half_b, c)
+        };
+        if let Some((t0, t1)) = roots {
+            for t in [t0, t1] {
+                let point = local_ray.at(t);
+                if !(0. ..=self.height).contains(&point.z) {
+                    continue;
+                }
+                // The gradient of the implicit surface.
+                let radius = self.base_radius + slope * point.z;
+                let gradient = Vec3::new(point.x, point.y, -slope * radius);
+                let normal = if gradient.near_zero() {
+                    // Right at the apex.
+                    Vec3::new(0., 0., 1.)
+                } else {
+                    gradient.normalized()
+                };
+                let candidate = LocalHit {
+                    t,
+                    normal,
+                    u: azimuth(&point),
+                    v: point.z / self.height,
+                };
+                keep_closest(&mut closest, ray_t, candidate);
+            }
+        }
+
+        if self.capped {
+            let caps = [
+                (self.base_radius > 0.).then(|| cap_hit(&local_ray, 0., self.base_radius, -1.)),
+                (self.top_radius > 0.)
+                    .then(|| cap_hit(&local_ray, self.height, self.top_radius, 1.)),
+            ];
+            for candidate in caps.into_iter().flatten().flatten() {
+                keep_closest(&mut closest, ray_t, candidate);
+            }
+        }
+
+        match closest {
+            Some(hit) => HitResult::Hit(self.frame.hit_record(ray, &hit, &self.material)),
+            None => HitResult::Miss,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
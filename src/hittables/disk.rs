@@ -0,0 +1,99 @@
+use super::{
+    aabb::Aabb,
+    hittable::{HitRecord, HitResult, Hittable},
+    plane::intersect_plane,
+    triangle::area_to_solid_angle_pdf,
+};
+use crate::{material::material::Material, my_math::prelude::*};
+use std::sync::Arc;
+
+// How far the rim of a disk with the given unit normal reaches from its center along each
+// axis: radius * sin(angle between the axis and the normal).
+pub fn disk_extent(normal: &Vec3, radius: f64) -> Vec3 {
+    Vec3::new(
+        radius * (1. - normal.x * normal.x).max(0.).sqrt(),
+        radius * (1. - normal.y * normal.y).max(0.).sqrt(),
+        radius * (1. - normal.z * normal.z).max(0.).sqrt(),
+    )
+}
+
+// Flat round disk facing along `normal`.
+pub struct Disk {
+    center: Point3,
+    radius: f64,
+    normal: Vec3,
+    d: f64,
+    // Axes of the (u, v) coordinates in the disk's plane.
+    axes: Onb,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, material: Arc<dyn Material>) -> Self {
+        let normal = normal.normalized();
+        let extent = disk_extent(&normal, radius);
+        Disk {
+            center,
+            radius,
+            normal,
+            d: normal.dot(&center),
+            axes: Onb::new(&normal),
+            material,
+            bbox: Aabb::new(center - extent, center + extent),
+        }
+    }
+
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn intersect(&self, ray: &Ray, ray_t: &Interval) -> Option<f64> {
+        let t = intersect_plane(ray, ray_t, &self.normal, self.d)?;
+        let offset = ray.at(t) - self.center;
+        (offset.length_squared() <= self.radius * self.radius).then_some(t)
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        let Some(t) = self.intersect(ray, ray_t) else {
+            return HitResult::Miss;
+        };
+
+        let mut rec = HitRecord::empty();
+        rec.t = t;
+        rec.intersection_point = ray.at(t);
+        rec.set_face_normal(ray, &self.normal);
+        // Textures are mapped onto the square around the disk, like a label.
+        let offset = rec.intersection_point - self.center;
+        rec.u = 0.5 + offset.dot(&self.axes.u) / (2. * self.radius);
+        rec.v = 0.5 + offset.dot(&self.axes.v) / (2. * self.radius);
+        rec.material = Arc::clone(&self.material);
+        HitResult::Hit(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.material.is_emissive() && self.radius > 0.
+    }
+
+    fn sample_direction(&self, origin: &Point3, _time: f64) -> Vec3 {
+        // The square root keeps the points uniform over the area rather than the radius.
+        let r = self.radius * random_double().sqrt();
+        let phi = 2. * PI * random_double();
+        let point = self.center + self.axes.u * (r * phi.cos()) + self.axes.v * (r * phi.sin());
+        (point - *origin).normalized()
+    }
+
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3, time: f64) -> f64 {
+        let ray = Ray::new(*origin, *direction, time);
+        match self.intersect(&ray, &Interval::new(0.001, INFINITY)) {
+            Some(t) => area_to_solid_angle_pdf(direction, t, &self.normal, self.area()),
+            None => 0.,
+        }
+    }
+}
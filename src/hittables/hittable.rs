@@ -1,3 +1,4 @@
+use super::aabb::Aabb;
 use crate::{
     color::Color,
     material::material::{Lambertian, Material},
@@ -11,11 +12,15 @@ pub struct HitRecord {
     pub normal: Vec3,
     pub material: Arc<dyn Material>,
     pub t: f64,
+    // Surface coordinates of the hit point, used for texturing.
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
+    // Index of the hit object in the list or BVH it belongs to. Containers overwrite it on
+    // the way out, so the camera sees the index in the outermost one, the world itself.
+    pub object_id: usize,
 }
 
-pub struct HitRecordNoHit;
-
 pub enum HitResult {
     Hit(HitRecord),
     Miss,
@@ -27,7 +32,7 @@ impl HitRecord {
         // NOTE: the parameter `outward_normal` is assumed to have unit length
 
         self.front_face = ray.direction.dot(outward_normal) < 0.;
-        self.normal.clone_from(&outward_normal);
+        self.normal.clone_from(outward_normal);
         if !self.front_face {
             self.normal = -self.normal;
         }
@@ -38,11 +43,34 @@ impl HitRecord {
             normal: Vec3::new(0., 0., 0.),
             material: Arc::new(Lambertian::new(Color::new(1., 1., 1.))),
             t: 0.,
+            u: 0.,
+            v: 0.,
             front_face: false,
+            object_id: 0,
         }
     }
 }
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult;
+    fn bounding_box(&self) -> Aabb;
+
+    // Whether the object is made of an emissive material, so that integrators can aim
+    // at it. Emitters implement `sample_direction` and `direction_pdf` as well.
+    fn is_emitter(&self) -> bool {
+        false
+    }
+
+    // Containers add their emitters, looking into nested containers.
+    fn collect_emitters(&self, _emitters: &mut Vec<Arc<dyn Hittable>>) {}
+
+    // A random unit vector from `origin` towards a point of the object, and the density,
+    // over solid angle, with which that picks `direction`. The density is zero if
+    // `direction` misses the object. Both look at moving objects where they are at `time`.
+    fn sample_direction(&self, _origin: &Point3, _time: f64) -> Vec3 {
+        Vec3::new(1., 0., 0.)
+    }
+    fn direction_pdf(&self, _origin: &Point3, _direction: &Vec3, _time: f64) -> f64 {
+        0.
+    }
 }
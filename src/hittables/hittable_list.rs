@@ -1,43 +1,57 @@
 use crate::my_math::prelude::*;
 
-use super::hittable::{HitResult, Hittable};
-use std::rc::Rc;
+use super::{
+    aabb::Aabb,
+    hittable::{HitResult, Hittable},
+};
+use std::sync::Arc;
 
 pub struct HittableList {
-    objects: Vec<Rc<dyn Hittable>>,
+    objects: Vec<Arc<dyn Hittable>>,
+    bbox: Aabb,
 }
 
 impl HittableList {
     pub fn new_empty() -> Self {
         HittableList {
             objects: Vec::new(),
+            bbox: Aabb::EMPTY,
         }
     }
-    pub fn new(hittable: Rc<dyn Hittable>) -> Self {
+    pub fn new(hittable: Arc<dyn Hittable>) -> Self {
         let mut list = HittableList::new_empty();
         list.add(hittable);
         list
     }
-    pub fn add(&mut self, hittable: Rc<dyn Hittable>) {
+    pub fn add(&mut self, hittable: Arc<dyn Hittable>) {
+        self.bbox = Aabb::surrounding(&self.bbox, &hittable.bounding_box());
         self.objects.push(hittable);
     }
     pub fn clear(&mut self) {
         self.objects.clear();
+        self.bbox = Aabb::EMPTY;
+    }
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+    pub fn into_objects(self) -> Vec<Arc<dyn Hittable>> {
+        self.objects
     }
 }
 
-unsafe impl Send for HittableList {}
-unsafe impl Sync for HittableList {}
-
 impl Hittable for HittableList {
     fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
         let mut closest_so_far = ray_t.max;
         let mut hit_result = HitResult::Miss;
 
-        for obj in self.objects.iter() {
+        for (object_id, obj) in self.objects.iter().enumerate() {
             match obj.hit(ray, &Interval::new(ray_t.min, closest_so_far)) {
-                HitResult::Hit(x) => {
+                HitResult::Hit(mut x) => {
                     closest_so_far = x.t;
+                    x.object_id = object_id;
                     hit_result = HitResult::Hit(x);
                 }
                 HitResult::Miss => (),
@@ -45,4 +59,18 @@ impl Hittable for HittableList {
         }
         hit_result
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn collect_emitters(&self, emitters: &mut Vec<Arc<dyn Hittable>>) {
+        for object in &self.objects {
+            if object.is_emitter() {
+                emitters.push(Arc::clone(object));
+            } else {
+                object.collect_emitters(emitters);
+            }
+        }
+    }
 }
@@ -0,0 +1,258 @@
+use super::{
+    aabb::Aabb,
+    hittable::{HitResult, Hittable},
+};
+use crate::my_math::prelude::*;
+use std::sync::Arc;
+
+// An object placed in the world by a transform. The object itself is shared, so any
+// number of instances of one mesh only store the mesh once. Rays are moved into the
+// object's own space to be intersected, and the hits are moved back out.
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    motion: Motion,
+    bbox: Aabb,
+}
+
+// From object space to world space.
+#[derive(Clone, Copy)]
+enum Motion {
+    Fixed(Transform),
+    // Moving by `offset` from time 0 to time 1, still before and after. Only the
+    // translation changes, so the transform is built once and shifted as it goes.
+    Sliding { start: Transform, offset: Vec3 },
+    // From `start` at time 0 to `end` at time 1, still before and after.
+    Moving { start: Placement, end: Placement },
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Self {
+        Instance::with_motion(object, Motion::Fixed(transform))
+    }
+
+    // An instance that moves from the `start` placement at time 0 to `end` at time 1. The
+    // camera shutter picks which part of that motion is seen.
+    pub fn moving(object: Arc<dyn Hittable>, start: Placement, end: Placement) -> Self {
+        let motion = if (end.scale - start.scale).near_zero()
+            && (end.rotation - start.rotation).near_zero()
+        {
+            Motion::Sliding {
+                start: start.transform(),
+                offset: end.translation - start.translation,
+            }
+        } else {
+            Motion::Moving { start, end }
+        };
+        Instance::with_motion(object, motion)
+    }
+
+    fn with_motion(object: Arc<dyn Hittable>, motion: Motion) -> Self {
+        let object_bbox = object.bounding_box();
+        let bbox = match &motion {
+            Motion::Fixed(transform) => transformed_bounding_box(&object_bbox, transform),
+            // The translation is linear, so the ends bound the whole path.
+            Motion::Sliding { start, offset } => Aabb::surrounding(
+                &transformed_bounding_box(&object_bbox, start),
+                &transformed_bounding_box(&object_bbox, &start.translated(*offset)),
+            ),
+            Motion::Moving { start, end } => moving_bounding_box(&object_bbox, start, end),
+        };
+        Instance {
+            object,
+            motion,
+            bbox,
+        }
+    }
+
+    fn transform_at(&self, time: f64) -> Transform {
+        match &self.motion {
+            Motion::Fixed(transform) => *transform,
+            Motion::Sliding { start, offset } => start.translated(*offset * time.clamp(0., 1.)),
+            Motion::Moving { start, end } => start.lerp(end, time.clamp(0., 1.)).transform(),
+        }
+    }
+}
+
+// The world space box around the eight transformed corners of `bbox`.
+fn transformed_bounding_box(bbox: &Aabb, transform: &Transform) -> Aabb {
+    if bbox.is_empty() {
+        return Aabb::EMPTY;
+    }
+    if !bbox.is_bounded() {
+        // Infinite extents do not survive a rotation as anything narrower.
+        return Aabb::UNIVERSE;
+    }
+    let mut result = Aabb::EMPTY;
+    for point in corners(bbox) {
+        let point = transform.point(&point);
+        result = Aabb::surrounding(&result, &Aabb::new(point, point));
+    }
+    result
+}
+
+// A box around everywhere the object goes between two placements.
+fn moving_bounding_box(bbox: &Aabb, start: &Placement, end: &Placement) -> Aabb {
+    let start_bbox = transformed_bounding_box(bbox, &start.transform());
+    if !start_bbox.is_bounded() || start_bbox.is_empty() {
+        return start_bbox;
+    }
+    // Rotating corners sweep arcs, so instead bound every point of the box by its
+    // distance from the object's origin, scaled up as much as the object ever is, around
+    // the straight path of the translation.
+    let distance = corners(bbox)
+        .map(|corner| corner.length())
+        .fold(0., f64::max);
+    let scale = [start.scale, end.scale]
+        .iter()
+        .flat_map(|scale| [scale.x.abs(), scale.y.abs(), scale.z.abs()])
+        .fold(0., f64::max);
+    let reach = distance * scale;
+    let reach = Vec3::new(reach, reach, reach);
+    Aabb::surrounding(
+        &Aabb::new(start.translation - reach, start.translation + reach),
+        &Aabb::new(end.translation - reach, end.translation + reach),
+    )
+}
+
+fn corners(bbox: &Aabb) -> impl Iterator<Item = Point3> + '_ {
+    (0..8).map(move |corner| {
+        let pick = |interval: &Interval, bit: usize| {
+            if corner & (1 << bit) == 0 {
+                interval.min
+            } else {
+                interval.max
+            }
+        };
+        Point3::new(pick(&bbox.x, 0), pick(&bbox.y, 1), pick(&bbox.z, 2))
+    })
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        let transform = self.transform_at(ray.time);
+        let local_ray = transform.inverse().ray(ray);
+        match self.object.hit(&local_ray, ray_t) {
+            HitResult::Hit(mut rec) => {
+                // `t` carries over unchanged. The normal already faces the ray in object
+                // space, and the inverse transpose keeps it on the same side.
+                rec.intersection_point = ray.at(rec.t);
+                rec.normal = transform.normal(&rec.normal).normalized();
+                HitResult::Hit(rec)
+            }
+            HitResult::Miss => HitResult::Miss,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.object.is_emitter()
+    }
+
+    // The emitters inside a transformed container are transformed as well.
+    fn collect_emitters(&self, emitters: &mut Vec<Arc<dyn Hittable>>) {
+        let mut local_emitters = Vec::new();
+        self.object.collect_emitters(&mut local_emitters);
+        emitters.extend(local_emitters.into_iter().map(|emitter| {
+            Arc::new(Instance::with_motion(emitter, self.motion)) as Arc<dyn Hittable>
+        }));
+    }
+
+    fn sample_direction(&self, origin: &Point3, time: f64) -> Vec3 {
+        let transform = self.transform_at(time);
+        let local_origin = transform.inverse().point(origin);
+        transform
+            .vector(&self.object.sample_direction(&local_origin, time))
+            .normalized()
+    }
+
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3, time: f64) -> f64 {
+        let transform = self.transform_at(time);
+        let inverse = transform.inverse();
+        let local_direction = inverse.vector(direction).normalized();
+        let local_pdf = self
+            .object
+            .direction_pdf(&inverse.point(origin), &local_direction, time);
+        // Directions are spread over solid angle by the Jacobian |det M| / |M d|^3 of
+        // the map from a unit direction d to the normalized M d.
+        let stretch = transform.vector(&local_direction).length();
+        local_pdf * stretch * stretch * stretch / transform.determinant().abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, hittables::sphere::Sphere, material::material::Lambertian};
+
+    // A unit sphere squashed into an ellipsoid, turned and moved off the origin.
+    fn ellipsoid() -> Instance {
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let sphere = Arc::new(Sphere::new(Point3::new(0., 0., 0.), 1., material));
+        let placement = Placement {
+            scale: Vec3::new(3., 1., 0.5),
+            rotation: Vec3::new(20., -40., 70.),
+            translation: Vec3::new(1., 2., 6.),
+        };
+        Instance::new(sphere, placement.transform())
+    }
+
+    fn hits(object: &dyn Hittable, origin: &Point3, direction: &Vec3) -> bool {
+        let ray = Ray::new(*origin, *direction, 0.);
+        matches!(
+            object.hit(&ray, &Interval::new(0.001, INFINITY)),
+            HitResult::Hit(_)
+        )
+    }
+
+    #[test]
+    fn direction_pdf_matches_sample_direction() {
+        seed_thread_rng(7);
+        let instance = ellipsoid();
+        let origin = Point3::new(0., 0., 0.);
+        let weights: [fn(&Vec3) -> f64; 3] = [|_| 1., |d| d.x * d.x, |d| (d.z - d.y).max(0.)];
+        let samples = 200_000;
+
+        // Integrals over the directions that hit the instance, once with directions drawn
+        // uniformly over the sphere, and once with those from `sample_direction`
+        // weighted by the inverse of `direction_pdf`.
+        let mut uniform = [0.; 3];
+        let mut sampled = [0.; 3];
+        let mut pdf_integral = 0.;
+        for _ in 0..samples {
+            let direction = Vec3::random_unit_vector();
+            if hits(&instance, &origin, &direction) {
+                for (sum, weight) in uniform.iter_mut().zip(weights) {
+                    *sum += 4. * PI * weight(&direction);
+                }
+            }
+            pdf_integral += 4. * PI * instance.direction_pdf(&origin, &direction, 0.);
+
+            let direction = instance.sample_direction(&origin, 0.);
+            assert!((direction.length() - 1.).abs() < 1e-9);
+            assert!(hits(&instance, &origin, &direction));
+            let pdf = instance.direction_pdf(&origin, &direction, 0.);
+            for (sum, weight) in sampled.iter_mut().zip(weights) {
+                *sum += weight(&direction) / pdf;
+            }
+        }
+
+        let pdf_integral = pdf_integral / samples as f64;
+        assert!(
+            (pdf_integral - 1.).abs() < 0.03,
+            "the pdf integrates to {}",
+            pdf_integral
+        );
+        for (uniform, sampled) in uniform.iter().zip(sampled) {
+            let (uniform, sampled) = (uniform / samples as f64, sampled / samples as f64);
+            assert!(
+                (uniform - sampled).abs() < 0.03 * uniform,
+                "{} with uniform directions, {} with sampled ones",
+                uniform,
+                sampled
+            );
+        }
+    }
+}
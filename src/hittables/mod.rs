@@ -2,3 +2,16 @@ pub mod hittable;
 pub mod sphere;
 pub mod hittable_list;
 pub mod prelude;
+pub mod aabb;
+pub mod bvh;
+pub mod triangle;
+pub mod triangle_mesh;
+pub mod plane;
+pub mod quad;
+pub mod disk;
+pub mod axis_frame;
+pub mod cylinder;
+pub mod capsule;
+pub mod torus;
+pub mod instance;
+pub mod constant_medium;
@@ -0,0 +1,64 @@
+use super::{
+    aabb::Aabb,
+    hittable::{HitRecord, HitResult, Hittable},
+};
+use crate::{material::material::Material, my_math::prelude::*};
+use std::sync::Arc;
+
+// Ray parameter at which the ray crosses the plane of the points p with
+// `normal.dot(p) == d`, if it is inside `ray_t`. Rays parallel to the plane miss it.
+pub fn intersect_plane(ray: &Ray, ray_t: &Interval, normal: &Vec3, d: f64) -> Option<f64> {
+    let denominator = normal.dot(&ray.direction);
+    if denominator.abs() < 1e-8 {
+        return None;
+    }
+    let t = (d - normal.dot(&ray.origin)) / denominator;
+    ray_t.surrounds(t).then_some(t)
+}
+
+// An infinite plane through `point`. Its bounding box is infinite too, which the BVH
+// handles by keeping it out of the tree.
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    d: f64,
+    // Axes of the (u, v) coordinates in the plane.
+    axes: Onb,
+    material: Arc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: Arc<dyn Material>) -> Self {
+        let normal = normal.normalized();
+        Plane {
+            point,
+            normal,
+            d: normal.dot(&point),
+            axes: Onb::new(&normal),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        let Some(t) = intersect_plane(ray, ray_t, &self.normal, self.d) else {
+            return HitResult::Miss;
+        };
+
+        let mut rec = HitRecord::empty();
+        rec.t = t;
+        rec.intersection_point = ray.at(t);
+        rec.set_face_normal(ray, &self.normal);
+        // The plane has no natural extent, so textures repeat every unit of distance.
+        let offset = rec.intersection_point - self.point;
+        rec.u = offset.dot(&self.axes.u).rem_euclid(1.);
+        rec.v = offset.dot(&self.axes.v).rem_euclid(1.);
+        rec.material = Arc::clone(&self.material);
+        HitResult::Hit(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::UNIVERSE
+    }
+}
@@ -1 +1 @@
-pub use super::{hittable::*, hittable_list::*};
+pub use super::{aabb::*, bvh::*, hittable::*, hittable_list::*};
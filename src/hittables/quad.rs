@@ -0,0 +1,116 @@
+use super::{
+    aabb::Aabb,
+    hittable::{HitRecord, HitResult, Hittable},
+    hittable_list::HittableList,
+    plane::intersect_plane,
+    triangle::area_to_solid_angle_pdf,
+};
+use crate::{material::material::Material, my_math::prelude::*};
+use std::sync::Arc;
+
+// Parallelogram with one corner at `q` and the two sides `u` and `v` starting from it.
+// The front face is the one `u` x `v` points out of.
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    // `u` x `v` divided by its squared length, which turns a point of the plane into its
+    // coordinates along `u` and `v`.
+    w: Vec3,
+    normal: Vec3,
+    d: f64,
+    area: f64,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        let n = u.cross(&v);
+        let normal = n.normalized();
+        // The box of the two diagonals holds all four corners.
+        let bbox = Aabb::surrounding(&Aabb::new(q, q + u + v), &Aabb::new(q + u, q + v));
+        Quad {
+            q,
+            u,
+            v,
+            w: n / n.length_squared(),
+            normal,
+            d: normal.dot(&q),
+            area: n.length(),
+            material,
+            bbox,
+        }
+    }
+
+    // Where the ray crosses the quad: the ray parameter and the coordinates along `u`
+    // and `v`, which double as the surface (u, v).
+    fn intersect(&self, ray: &Ray, ray_t: &Interval) -> Option<(f64, f64, f64)> {
+        let t = intersect_plane(ray, ray_t, &self.normal, self.d)?;
+        let planar = ray.at(t) - self.q;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        let unit = Interval::new(0., 1.);
+        (unit.contains(alpha) && unit.contains(beta)).then_some((t, alpha, beta))
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        let Some((t, alpha, beta)) = self.intersect(ray, ray_t) else {
+            return HitResult::Miss;
+        };
+
+        let mut rec = HitRecord::empty();
+        rec.t = t;
+        rec.intersection_point = ray.at(t);
+        rec.set_face_normal(ray, &self.normal);
+        (rec.u, rec.v) = (alpha, beta);
+        rec.material = Arc::clone(&self.material);
+        HitResult::Hit(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.material.is_emissive() && self.area > 0.
+    }
+
+    fn sample_direction(&self, origin: &Point3, _time: f64) -> Vec3 {
+        let point = self.q + self.u * random_double() + self.v * random_double();
+        (point - *origin).normalized()
+    }
+
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3, time: f64) -> f64 {
+        let ray = Ray::new(*origin, *direction, time);
+        match self.intersect(&ray, &Interval::new(0.001, INFINITY)) {
+            Some((t, _, _)) => area_to_solid_angle_pdf(direction, t, &self.normal, self.area),
+            None => 0.,
+        }
+    }
+}
+
+// The box with opposite corners `a` and `b`, as six quads facing outwards.
+pub fn make_box(a: Point3, b: Point3, material: Arc<dyn Material>) -> HittableList {
+    let min = Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+    let dx = Vec3::new(max.x - min.x, 0., 0.);
+    let dy = Vec3::new(0., max.y - min.y, 0.);
+    let dz = Vec3::new(0., 0., max.z - min.z);
+
+    let faces = [
+        (Point3::new(min.x, min.y, max.z), dx, dy),  // front, +z
+        (Point3::new(max.x, min.y, max.z), -dz, dy), // right, +x
+        (Point3::new(max.x, min.y, min.z), -dx, dy), // back, -z
+        (Point3::new(min.x, min.y, min.z), dz, dy),  // left, -x
+        (Point3::new(min.x, max.y, max.z), dx, -dz), // top, +y
+        (Point3::new(min.x, min.y, min.z), dx, dz),  // bottom, -y
+    ];
+    let mut sides = HittableList::new_empty();
+    for (q, u, v) in faces {
+        sides.add(Arc::new(Quad::new(q, u, v, Arc::clone(&material))));
+    }
+    sides
+}
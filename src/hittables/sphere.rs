@@ -1,21 +1,54 @@
-use super::hittable::{HitRecord, HitResult, Hittable};
+use super::{
+    aabb::Aabb,
+    hittable::{HitRecord, HitResult, Hittable},
+};
 use crate::{material::material::Material, my_math::prelude::*};
 use std::sync::Arc;
 
 pub struct Sphere {
+    // Where the center is at time 0, and how far it moves until time 1.
     center: Point3,
+    motion: Vec3,
     radius: f64,
     material: Arc<dyn Material>,
+    bbox: Aabb,
 }
 
 impl Sphere {
     pub fn new(center: Point3, radius: f64, material: Arc<dyn Material>) -> Self {
+        Sphere::moving(center, center, radius, material)
+    }
+
+    // Sphere moving in a straight line from `start` at time 0 to `end` at time 1. It stays
+    // at `start` before and at `end` after.
+    pub fn moving(start: Point3, end: Point3, radius: f64, material: Arc<dyn Material>) -> Self {
+        // Negative radii are used for hollow glass spheres, so the box has to use |radius|.
+        let radius_vec = Vec3::new(radius.abs(), radius.abs(), radius.abs());
         Sphere {
-            center,
+            center: start,
+            motion: end - start,
             radius,
             material: Arc::clone(&material),
+            bbox: Aabb::surrounding(
+                &Aabb::new(start - radius_vec, start + radius_vec),
+                &Aabb::new(end - radius_vec, end + radius_vec),
+            ),
         }
     }
+
+    fn center_at(&self, time: f64) -> Point3 {
+        self.center + self.motion * time.clamp(0., 1.)
+    }
+}
+
+impl Sphere {
+    // Maps a point on the unit sphere to (u, v), both in [0, 1]:
+    // u is the angle around the Y axis starting from -X, v the angle from -Y to +Y.
+    fn uv(point: Point3) -> (f64, f64) {
+        let theta = (-point.y).clamp(-1., 1.).acos();
+        let phi = (-point.z).atan2(point.x) + PI;
+        (phi / (2. * PI), theta / PI)
+    }
 }
 
 impl Hittable for Sphere {
@@ -30,7 +63,8 @@ impl Hittable for Sphere {
         // It does hit the ray if delta >= 0.
         // Delta = (2 ray.dir (ray.org - center))^ 2 - 4 * (ray.dir^2) (ray.org - center) ^ 2
 
-        let oc = ray.origin - self.center;
+        let center = self.center_at(ray.time);
+        let oc = ray.origin - center;
 
         let a = ray.direction.length_squared();
         let half_b = ray.direction.dot(&oc);
@@ -52,10 +86,49 @@ impl Hittable for Sphere {
         let mut rec = HitRecord::empty();
         rec.t = root;
         rec.intersection_point = ray.at(rec.t);
-        let outward_normal = (rec.intersection_point - self.center) / self.radius;
+        let outward_normal = (rec.intersection_point - center) / self.radius;
         rec.set_face_normal(ray, &outward_normal);
+        (rec.u, rec.v) = Sphere::uv((rec.intersection_point - center) / self.radius.abs());
         rec.material = Arc::clone(&self.material);
 
         HitResult::Hit(rec)
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    // Uniform over the cone of directions in which the sphere is seen from `origin`.
+    fn sample_direction(&self, origin: &Point3, time: f64) -> Vec3 {
+        let to_center = self.center_at(time) - *origin;
+        let distance_squared = to_center.length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            // Inside the sphere every direction hits it.
+            return Vec3::random_unit_vector();
+        }
+
+        let cos_theta_max = (1. - radius_squared / distance_squared).sqrt();
+        Onb::new(&to_center).local(&Vec3::random_in_cone(cos_theta_max))
+    }
+
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3, time: f64) -> f64 {
+        let ray = Ray::new(*origin, *direction, time);
+        if let HitResult::Miss = self.hit(&ray, &Interval::new(0.001, INFINITY)) {
+            return 0.;
+        }
+
+        let distance_squared = (self.center_at(time) - *origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return 1. / (4. * PI);
+        }
+        let cos_theta_max = (1. - radius_squared / distance_squared).sqrt();
+        let solid_angle = 2. * PI * (1. - cos_theta_max);
+        1. / solid_angle
+    }
 }
@@ -0,0 +1,200 @@
+use super::{
+    aabb::Aabb,
+    axis_frame::{azimuth, AxisFrame, LocalHit},
+    disk::disk_extent,
+    hittable::{HitResult, Hittable},
+};
+use crate::{material::material::Material, my_math::prelude::*};
+use std::sync::Arc;
+
+// A ring: the points at `minor_radius` from the circle of `major_radius` around `center`
+// in the plane perpendicular to `axis`. It is textured with u around the axis and v
+// around the tube, starting on its outer side.
+pub struct Torus {
+    frame: AxisFrame,
+    major_radius: f64,
+    minor_radius: f64,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let frame = AxisFrame::new(center, &axis);
+        let extent = disk_extent(&frame.axes.w, major_radius)
+            + Vec3::new(minor_radius, minor_radius, minor_radius);
+        Torus {
+            frame,
+            major_radius,
+            minor_radius,
+            material,
+            bbox: Aabb::new(center - extent, center + extent),
+        }
+    }
+
+    // Ray parameters, in the units of the unit `direction`, where the local ray from
+    // `origin` crosses the torus.
+    fn intersections(&self, origin: &Point3, direction: &Vec3) -> Vec<f64> {
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + y^2) with p = origin + t direction is a
+        // quartic in t.
+        let (r2, big_r2) = (
+            self.minor_radius * self.minor_radius,
+            self.major_radius * self.major_radius,
+        );
+        let h = 2. * origin.dot(direction);
+        let i = origin.length_squared() + big_r2 - r2;
+        let coefficients = [
+            i * i - 4. * big_r2 * (origin.x * origin.x + origin.y * origin.y),
+            2. * h * i - 8. * big_r2 * (origin.x * direction.x + origin.y * direction.y),
+            h * h + 2. * i - 4. * big_r2 * (direction.x * direction.x + direction.y * direction.y),
+            2. * h,
+            1.,
+        ];
+        solve_quartic(coefficients)
+            .into_iter()
+            .map(|t| polish_root(&coefficients, t))
+            .collect()
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        let local_ray = self.frame.ray_to_local(ray);
+        let speed = local_ray.direction.length();
+        let direction = local_ray.direction / speed;
+
+        // Rays that miss the bounding sphere cannot hit the torus. The others start the
+        // solve from close to it: the quartic loses precision far away.
+        let bounding_radius = self.major_radius + self.minor_radius;
+        let closest_approach = -local_ray.origin.dot(&direction);
+        let miss_distance_squared =
+            (local_ray.origin + direction * closest_approach).length_squared();
+        if miss_distance_squared > bounding_radius * bounding_radius {
+            return HitResult::Miss;
+        }
+        let shift = (closest_approach - bounding_radius).max(0.);
+        let origin = local_ray.origin + direction * shift;
+
+        let closest = self
+            .intersections(&origin, &direction)
+            .into_iter()
+            .map(|distance| (distance + shift) / speed)
+            .filter(|&t| ray_t.surrounds(t))
+            .min_by(f64::total_cmp);
+        let Some(t) = closest else {
+            return HitResult::Miss;
+        };
+
+        let point = local_ray.at(t);
+        let distance_from_axis = (point.x * point.x + point.y * point.y).sqrt();
+        let on_ring = if distance_from_axis > 0. {
+            Vec3::new(point.x, point.y, 0.) * (self.major_radius / distance_from_axis)
+        } else {
+            Vec3::new(self.major_radius, 0., 0.)
+        };
+        let hit = LocalHit {
+            t,
+            normal: (point - on_ring) / self.minor_radius,
+            u: azimuth(&point),
+            v: (point.z.atan2(distance_from_axis - self.major_radius) / (2. * PI)).rem_euclid(1.),
+        };
+        HitResult::Hit(self.frame.hit_record(ray, &hit, &self.material))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::material::Lambertian};
+
+    // A ring around the z axis: the tube circle of radius 0.5 is centered 2 from the axis.
+    fn ring() -> Torus {
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Torus::new(
+            Point3::new(0., 0., 0.),
+            Vec3::new(0., 0., 1.),
+            2.,
+            0.5,
+            material,
+        )
+    }
+
+    // Where the ray from (distance, y, z) along -x first hits the ring.
+    fn hit_distance(distance: f64, y: f64, z: f64) -> Option<f64> {
+        let ray = Ray::new(Point3::new(distance, y, z), Vec3::new(-1., 0., 0.), 0.);
+        match ring().hit(&ray, &Interval::new(0.001, INFINITY)) {
+            HitResult::Hit(rec) => Some(rec.t),
+            HitResult::Miss => None,
+        }
+    }
+
+    // The same, worked out directly: at height z the ring is the annulus around the axis
+    // out to 2 + sqrt(0.5^2 - z^2), and the ray meets its outer edge first.
+    fn expected_distance(distance: f64, y: f64, z: f64) -> f64 {
+        let outer_radius = 2. + (0.25 - z * z).sqrt();
+        distance - (outer_radius * outer_radius - y * y).sqrt()
+    }
+
+    fn assert_hit(distance: f64, y: f64, z: f64, tolerance: f64) {
+        let expected = expected_distance(distance, y, z);
+        match hit_distance(distance, y, z) {
+            Some(t) => assert!(
+                (t - expected).abs() <= tolerance,
+                "ray from ({}, {}, {}) hit at {} instead of {}",
+                distance,
+                y,
+                z,
+                t,
+                expected
+            ),
+            None => panic!(
+                "ray from ({}, {}, {}) missed, expected a hit at {}",
+                distance, y, z, expected
+            ),
+        }
+    }
+
+    #[test]
+    fn hit_from_far_away() {
+        for distance in [10., 1e3, 1e6] {
+            for (y, z) in [(0., 0.), (0., 0.3), (1.5, -0.45), (2.2, 0.1)] {
+                assert_hit(distance, y, z, 1e-9 * distance);
+            }
+        }
+    }
+
+    #[test]
+    fn grazing_hits() {
+        // Just under the top or bottom of the tube, where the two hits on the outer side
+        // are close to being a double root.
+        for depth in [1e-3, 1e-5, 1e-7] {
+            for z in [0.5 - depth, depth - 0.5] {
+                for y in [0., 0.7, 1.9] {
+                    assert_hit(1e3, y, z, 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn misses() {
+        // Over the top of the tube, past its outer edge, and through the hole.
+        assert_eq!(hit_distance(1e3, 0., 0.501), None);
+        assert_eq!(hit_distance(1e3, 2.501, 0.), None);
+        let through = Ray::new(Point3::new(0., 0., 1e3), Vec3::new(0., 0., -1.), 0.);
+        assert!(matches!(
+            ring().hit(&through, &Interval::new(0.001, INFINITY)),
+            HitResult::Miss
+        ));
+    }
+}
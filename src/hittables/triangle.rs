@@ -0,0 +1,215 @@
+use super::{
+    aabb::Aabb,
+    hittable::{HitRecord, HitResult, Hittable},
+};
+use crate::{material::material::Material, my_math::prelude::*};
+use std::sync::Arc;
+
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: Option<[Vec3; 3]>,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+// Result of a ray-triangle test: the ray parameter and the barycentric coordinates
+// of the hit point with respect to the second and third vertex.
+pub struct TriangleIntersection {
+    pub t: f64,
+    pub u: f64,
+    pub v: f64,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3, material: Arc<dyn Material>) -> Self {
+        Triangle {
+            vertices: [a, b, c],
+            normals: None,
+            material,
+            bbox: triangle_bounding_box(&a, &b, &c),
+        }
+    }
+
+    // Triangle with per-vertex normals, which are interpolated across the face for smooth shading.
+    pub fn with_normals(
+        vertices: [Point3; 3],
+        normals: [Vec3; 3],
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let [a, b, c] = vertices;
+        Triangle {
+            vertices,
+            normals: Some(normals),
+            material,
+            bbox: triangle_bounding_box(&a, &b, &c),
+        }
+    }
+}
+
+pub fn triangle_bounding_box(a: &Point3, b: &Point3, c: &Point3) -> Aabb {
+    let min = Point3::new(
+        a.x.min(b.x).min(c.x),
+        a.y.min(b.y).min(c.y),
+        a.z.min(b.z).min(c.z),
+    );
+    let max = Point3::new(
+        a.x.max(b.x).max(c.x),
+        a.y.max(b.y).max(c.y),
+        a.z.max(b.z).max(c.z),
+    );
+    Aabb::new(min, max)
+}
+
+pub fn intersect_triangle(
+    ray: &Ray,
+    ray_t: &Interval,
+    a: &Point3,
+    b: &Point3,
+    c: &Point3,
+) -> Option<TriangleIntersection> {
+    // Möller–Trumbore: solve origin + t * direction == a + u * (b - a) + v * (c - a)
+    // for (t, u, v) with Cramer's rule, reusing the cross products between the terms.
+    const EPSILON: f64 = 1e-12;
+
+    let edge1 = *b - *a;
+    let edge2 = *c - *a;
+    let p = ray.direction.cross(&edge2);
+    let determinant = edge1.dot(&p);
+    if determinant.abs() < EPSILON {
+        // The ray is parallel to the triangle plane.
+        return None;
+    }
+    let inv_determinant = 1. / determinant;
+
+    let s = ray.origin - *a;
+    let u = s.dot(&p) * inv_determinant;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(&edge1);
+    let v = ray.direction.dot(&q) * inv_determinant;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+
+    let t = edge2.dot(&q) * inv_determinant;
+    if !ray_t.surrounds(t) {
+        return None;
+    }
+    Some(TriangleIntersection { t, u, v })
+}
+
+// Fills a hit record for a triangle hit. `normals` are the optional per-vertex normals;
+// without them the flat geometric normal is used. Likewise without per-vertex `texcoords`
+// the barycentric coordinates become the surface (u, v).
+pub fn triangle_hit_record(
+    ray: &Ray,
+    hit: &TriangleIntersection,
+    vertices: [&Point3; 3],
+    normals: Option<[&Vec3; 3]>,
+    texcoords: Option<[&(f64, f64); 3]>,
+    material: &Arc<dyn Material>,
+) -> HitRecord {
+    let [a, b, c] = vertices;
+    let geometric_normal = (*b - *a).cross(&(*c - *a)).normalized();
+
+    let mut rec = HitRecord::empty();
+    rec.t = hit.t;
+    rec.intersection_point = ray.at(hit.t);
+    // The side of the surface is always decided by the real geometry, the interpolated
+    // normal only changes how the surface is shaded.
+    rec.set_face_normal(ray, &geometric_normal);
+    if let Some([na, nb, nc]) = normals {
+        let w = 1. - hit.u - hit.v;
+        let shading_normal = (*na * w + *nb * hit.u + *nc * hit.v).normalized();
+        // Vertex normals do not always agree with the winding order, so the shading normal
+        // is flipped onto the side the ray came from.
+        rec.normal = if shading_normal.dot(&rec.normal) < 0. {
+            -shading_normal
+        } else {
+            shading_normal
+        };
+    }
+    (rec.u, rec.v) = match texcoords {
+        Some([ta, tb, tc]) => {
+            let w = 1. - hit.u - hit.v;
+            (
+                ta.0 * w + tb.0 * hit.u + tc.0 * hit.v,
+                ta.1 * w + tb.1 * hit.u + tc.1 * hit.v,
+            )
+        }
+        None => (hit.u, hit.v),
+    };
+    rec.material = Arc::clone(material);
+    rec
+}
+
+// A point distributed uniformly over the triangle.
+pub fn sample_triangle(a: &Point3, b: &Point3, c: &Point3) -> Point3 {
+    // Folding the unit square onto the triangle keeps the density uniform.
+    let (mut u, mut v) = (random_double(), random_double());
+    if u + v > 1. {
+        (u, v) = (1. - u, 1. - v);
+    }
+    *a + (*b - *a) * u + (*c - *a) * v
+}
+
+// Density over solid angle, as seen from `origin`, of picking `direction` when a point is
+// sampled uniformly over a surface of `area` and found at distance `t * |direction|` with
+// the given geometric normal.
+pub fn area_to_solid_angle_pdf(direction: &Vec3, t: f64, normal: &Vec3, area: f64) -> f64 {
+    let distance_squared = t * t * direction.length_squared();
+    let cosine = (direction.normalized().dot(normal)).abs();
+    if cosine < 1e-12 || area <= 0. {
+        0.
+    } else {
+        distance_squared / (cosine * area)
+    }
+}
+
+pub fn triangle_area(a: &Point3, b: &Point3, c: &Point3) -> f64 {
+    0.5 * (*b - *a).cross(&(*c - *a)).length()
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        let [a, b, c] = &self.vertices;
+        match intersect_triangle(ray, ray_t, a, b, c) {
+            Some(hit) => HitResult::Hit(triangle_hit_record(
+                ray,
+                &hit,
+                [a, b, c],
+                self.normals.as_ref().map(|[na, nb, nc]| [na, nb, nc]),
+                None,
+                &self.material,
+            )),
+            None => HitResult::Miss,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn sample_direction(&self, origin: &Point3, _time: f64) -> Vec3 {
+        let [a, b, c] = &self.vertices;
+        (sample_triangle(a, b, c) - *origin).normalized()
+    }
+
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3, time: f64) -> f64 {
+        let [a, b, c] = &self.vertices;
+        let ray = Ray::new(*origin, *direction, time);
+        match intersect_triangle(&ray, &Interval::new(0.001, INFINITY), a, b, c) {
+            Some(hit) => {
+                let normal = (*b - *a).cross(&(*c - *a)).normalized();
+                area_to_solid_angle_pdf(direction, hit.t, &normal, triangle_area(a, b, c))
+            }
+            None => 0.,
+        }
+    }
+}
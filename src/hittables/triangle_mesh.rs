@@ -0,0 +1,218 @@
+use super::{
+    aabb::Aabb,
+    bvh::BvhNode,
+    hittable::{HitResult, Hittable},
+    triangle::{
+        area_to_solid_angle_pdf, intersect_triangle, sample_triangle, triangle_area,
+        triangle_bounding_box, triangle_hit_record,
+    },
+};
+use crate::{material::material::Material, my_math::prelude::*};
+use std::sync::Arc;
+
+// Vertex and index buffers shared by every triangle of a mesh.
+struct MeshData {
+    positions: Vec<Point3>,
+    normals: Option<Vec<Vec3>>,
+    texcoords: Option<Vec<(f64, f64)>>,
+    indices: Vec<[usize; 3]>,
+    material: Arc<dyn Material>,
+}
+
+// A single face of a mesh. It only stores its face index, the vertices are looked up
+// in the shared buffers.
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    face: usize,
+}
+
+pub struct TriangleMesh {
+    mesh: Arc<MeshData>,
+    bvh: BvhNode,
+    // Running sums of the face areas, to sample faces in proportion to their area.
+    area_cdf: Vec<f64>,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Point3>,
+        indices: Vec<[usize; 3]>,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        TriangleMesh::with_attributes(positions, None, None, indices, material)
+    }
+
+    // Mesh with one normal per entry of `positions`, used for smooth shading.
+    pub fn with_normals(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        indices: Vec<[usize; 3]>,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        TriangleMesh::with_attributes(positions, Some(normals), None, indices, material)
+    }
+
+    // Mesh with optional per-vertex normals and texture coordinates. Each of them needs
+    // exactly one entry per entry of `positions`.
+    pub fn with_attributes(
+        positions: Vec<Point3>,
+        normals: Option<Vec<Vec3>>,
+        texcoords: Option<Vec<(f64, f64)>>,
+        indices: Vec<[usize; 3]>,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        if let Some(normals) = &normals {
+            assert_eq!(
+                positions.len(),
+                normals.len(),
+                "a mesh needs exactly one normal per vertex"
+            );
+        }
+        if let Some(texcoords) = &texcoords {
+            assert_eq!(
+                positions.len(),
+                texcoords.len(),
+                "a mesh needs exactly one texture coordinate per vertex"
+            );
+        }
+        if let Some(index) = indices.iter().flatten().find(|&&i| i >= positions.len()) {
+            panic!(
+                "mesh index {} is out of range for {} vertices",
+                index,
+                positions.len()
+            );
+        }
+
+        let mesh = Arc::new(MeshData {
+            positions,
+            normals,
+            texcoords,
+            indices,
+            material,
+        });
+        let faces: Vec<Arc<dyn Hittable>> = (0..mesh.indices.len())
+            .map(|face| {
+                Arc::new(MeshTriangle {
+                    mesh: Arc::clone(&mesh),
+                    face,
+                }) as Arc<dyn Hittable>
+            })
+            .collect();
+
+        let area_cdf = (0..mesh.indices.len())
+            .scan(0., |total, face| {
+                let [a, b, c] = mesh.face_vertices(face);
+                *total += triangle_area(a, b, c);
+                Some(*total)
+            })
+            .collect();
+
+        TriangleMesh {
+            mesh,
+            bvh: BvhNode::from_objects(faces),
+            area_cdf,
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.mesh.positions.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.mesh.indices.len()
+    }
+}
+
+impl MeshData {
+    fn face_vertices(&self, face: usize) -> [&Point3; 3] {
+        let [a, b, c] = self.indices[face];
+        [&self.positions[a], &self.positions[b], &self.positions[c]]
+    }
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [&Point3; 3] {
+        self.mesh.face_vertices(self.face)
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        let [a, b, c] = self.vertices();
+        match intersect_triangle(ray, ray_t, a, b, c) {
+            Some(hit) => {
+                let [ia, ib, ic] = self.mesh.indices[self.face];
+                let normals = self
+                    .mesh
+                    .normals
+                    .as_ref()
+                    .map(|normals| [&normals[ia], &normals[ib], &normals[ic]]);
+                let texcoords = self
+                    .mesh
+                    .texcoords
+                    .as_ref()
+                    .map(|texcoords| [&texcoords[ia], &texcoords[ib], &texcoords[ic]]);
+                HitResult::Hit(triangle_hit_record(
+                    ray,
+                    &hit,
+                    [a, b, c],
+                    normals,
+                    texcoords,
+                    &self.mesh.material,
+                ))
+            }
+            None => HitResult::Miss,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let [a, b, c] = self.vertices();
+        triangle_bounding_box(a, b, c)
+    }
+}
+
+impl TriangleMesh {
+    fn total_area(&self) -> f64 {
+        self.area_cdf.last().copied().unwrap_or(0.)
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, ray_t: &Interval) -> HitResult {
+        self.bvh.hit(ray, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.mesh.material.is_emissive() && self.total_area() > 0.
+    }
+
+    // Uniform over the whole surface of the mesh.
+    fn sample_direction(&self, origin: &Point3, _time: f64) -> Vec3 {
+        let target = random_double() * self.total_area();
+        let face = self
+            .area_cdf
+            .partition_point(|&sum| sum < target)
+            .min(self.area_cdf.len().saturating_sub(1));
+        let [a, b, c] = self.mesh.face_vertices(face);
+        (sample_triangle(a, b, c) - *origin).normalized()
+    }
+
+    // Only the closest face along `direction` is accounted for, which is exact for meshes
+    // that no ray from `origin` crosses twice, such as flat area lights.
+    fn direction_pdf(&self, origin: &Point3, direction: &Vec3, time: f64) -> f64 {
+        let ray = Ray::new(*origin, *direction, time);
+        match self.bvh.hit(&ray, &Interval::new(0.001, INFINITY)) {
+            HitResult::Hit(rec) => {
+                // The mesh BVH reports the face index as the object ID.
+                let [a, b, c] = self.mesh.face_vertices(rec.object_id);
+                let normal = (*b - *a).cross(&(*c - *a)).normalized();
+                area_to_solid_angle_pdf(direction, rec.t, &normal, self.total_area())
+            }
+            HitResult::Miss => 0.,
+        }
+    }
+}
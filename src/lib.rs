@@ -0,0 +1,11 @@
+pub mod camera;
+pub mod color;
+pub mod draw_image;
+pub mod hittables;
+pub mod light;
+pub mod material;
+pub mod my_math;
+pub mod scene;
+pub mod output;
+pub mod texture;
+pub mod render;
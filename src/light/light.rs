@@ -0,0 +1,162 @@
+use crate::{color::Color, my_math::prelude::*};
+
+// Light arriving at a point from an analytic light.
+pub struct LightSample {
+    // Unit vector from the lit point towards the light.
+    pub direction: Vec3,
+    // Distance to the light along `direction`, infinite for directional lights. Anything
+    // hit before that casts a shadow.
+    pub distance: f64,
+    // Light arriving per unit area facing `direction`. Multiplied by the BSDF times the
+    // cosine term it gives the light scattered towards the viewer.
+    pub irradiance: Color,
+}
+
+// A light that is not a surface: a point, or a direction infinitely far away. Rays never
+// hit these, so integrators can only find their light with shadow rays.
+pub trait Light: Send + Sync {
+    // The light reaching `point`, ignoring shadows, or None if it gets none at all.
+    fn sample(&self, point: &Point3) -> Option<LightSample>;
+}
+
+// Shines equally in all directions, fading with the square of the distance.
+pub struct PointLight {
+    position: Point3,
+    // Light given off per unit solid angle.
+    intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        PointLight {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: &Point3) -> Option<LightSample> {
+        let to_light = self.position - *point;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0. {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            irradiance: self.intensity / distance_squared,
+        })
+    }
+}
+
+// A point light that only shines into a cone around `direction`. The light fades out
+// smoothly over the outer `falloff_angle` degrees of the cone, or cuts off sharply if that
+// is zero.
+pub struct SpotLight {
+    position: Point3,
+    // Unit vector along the axis of the cone.
+    direction: Vec3,
+    intensity: Color,
+    // Cosines of the angles from the axis where the light starts fading and where it ends.
+    cos_falloff_start: f64,
+    cos_cone_angle: f64,
+}
+
+impl SpotLight {
+    // `cone_angle` is the angle between the axis and the edge of the cone, in degrees.
+    pub fn new(
+        position: Point3,
+        direction: Vec3,
+        intensity: Color,
+        cone_angle: f64,
+        falloff_angle: f64,
+    ) -> Self {
+        let falloff_angle = falloff_angle.clamp(0., cone_angle);
+        SpotLight {
+            position,
+            direction: direction.normalized(),
+            intensity,
+            cos_falloff_start: deg2rad(cone_angle - falloff_angle).cos(),
+            cos_cone_angle: deg2rad(cone_angle).cos(),
+        }
+    }
+
+    // Fraction of the intensity sent along a direction at an angle with cosine
+    // `cos_theta` from the axis.
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_falloff_start {
+            1.
+        } else if cos_theta <= self.cos_cone_angle {
+            0.
+        } else {
+            let x =
+                (cos_theta - self.cos_cone_angle) / (self.cos_falloff_start - self.cos_cone_angle);
+            x * x * (3. - 2. * x)
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: &Point3) -> Option<LightSample> {
+        let to_light = self.position - *point;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0. {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        let direction = to_light / distance;
+        let falloff = self.falloff(-direction.dot(&self.direction));
+        if falloff <= 0. {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            irradiance: self.intensity * (falloff / distance_squared),
+        })
+    }
+}
+
+// Light from infinitely far away, all travelling along `direction`, like sunlight. With
+// a nonzero angular diameter the light comes from a small disk in the sky instead of a
+// single direction, which softens the shadows.
+pub struct DirectionalLight {
+    // Unit vector from the lit points towards the light.
+    to_light: Vec3,
+    // Light arriving per unit area facing the light.
+    irradiance: Color,
+    // Cosine of half the angular diameter.
+    cos_radius: f64,
+}
+
+impl DirectionalLight {
+    // `direction` is the direction the light travels in, and `angular_diameter` is in
+    // degrees; the sun is about half a degree wide.
+    pub fn new(direction: Vec3, irradiance: Color, angular_diameter: f64) -> Self {
+        DirectionalLight {
+            to_light: -direction.normalized(),
+            irradiance,
+            cos_radius: deg2rad(angular_diameter.max(0.) / 2.).cos(),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: &Point3) -> Option<LightSample> {
+        // Picking a direction uniformly over the disk, the radiance it carries is the
+        // irradiance spread over the disk's solid angle, and dividing by the pdf cancels
+        // that out again: every sample brings the full irradiance.
+        let direction = if self.cos_radius < 1. {
+            Onb::new(&self.to_light).local(&Vec3::random_in_cone(self.cos_radius))
+        } else {
+            self.to_light
+        };
+        Some(LightSample {
+            direction,
+            distance: INFINITY,
+            irradiance: self.irradiance,
+        })
+    }
+}
@@ -1,12 +1,358 @@
-mod color;
-mod draw_image;
-mod hittables;
-mod my_math;
-mod camera;
-mod material;
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use draw_image::draw_image;
+use clap::{Parser, ValueEnum};
+use rand::SeedableRng;
+use rand_pcg::Pcg64Mcg;
+use ray_tracing::{
+    camera::{Camera, RenderOutput},
+    draw_image::{make_big_camera, make_big_render, make_small_camera, make_world},
+    hittables::prelude::*,
+    output::format::{save_image_as, ImageFormat},
+    render::{
+        adaptive::AdaptiveSampling,
+        aov::Aov,
+        integrator::{
+            AlbedoIntegrator, AmbientOcclusionIntegrator, DepthIntegrator, Integrator,
+            NormalsIntegrator, PathTracer, SimplePathTracer,
+        },
+        tile::TileOrder,
+    },
+    scene::scene_file::load_scene,
+};
 
-fn main() {
-    draw_image();
+#[derive(Clone, Copy, ValueEnum)]
+enum BuiltinScene {
+    // Three spheres on a green ground.
+    Spheres,
+    // Hundreds of small random spheres around three big ones.
+    Big,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum IntegratorKind {
+    // Full light transport, with light sampling.
+    Path,
+    // Full light transport, finding lights only by chance.
+    Simple,
+    // Debug views of the first hit.
+    Normals,
+    Depth,
+    Albedo,
+    #[value(name = "ao")]
+    AmbientOcclusion,
+}
+
+impl IntegratorKind {
+    // Debug integrators that need a length scale take it from the camera, as the distance
+    // between `look_from` and `look_at` roughly gives the size of the scene.
+    fn build(self, cam: &Camera) -> Arc<dyn Integrator> {
+        let scene_size = (cam.look_from - cam.look_at).length();
+        match self {
+            IntegratorKind::Path => Arc::new(PathTracer),
+            IntegratorKind::Simple => Arc::new(SimplePathTracer),
+            IntegratorKind::Normals => Arc::new(NormalsIntegrator),
+            IntegratorKind::Depth => Arc::new(DepthIntegrator {
+                max_distance: 2. * scene_size,
+            }),
+            IntegratorKind::Albedo => Arc::new(AlbedoIntegrator),
+            IntegratorKind::AmbientOcclusion => Arc::new(AmbientOcclusionIntegrator {
+                distance: 0.25 * scene_size,
+            }),
+        }
+    }
+}
+
+/// Renders a scene and saves the image.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// Scene file (TOML) to render; without it a built-in scene is rendered
+    scene: Option<PathBuf>,
+
+    /// Built-in scene to render when no scene file is given
+    #[arg(long, value_enum, default_value_t = BuiltinScene::Big, conflicts_with = "scene")]
+    builtin: BuiltinScene,
+
+    /// Output image; the format is taken from the extension unless --format is given
+    #[arg(short, long, default_value = "image.png")]
+    output: PathBuf,
+
+    /// Output format: png, png16, ppm or pfm
+    #[arg(short, long, value_parser = parse_format)]
+    format: Option<ImageFormat>,
+
+    /// Image width in pixels
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    width: Option<u64>,
+
+    /// Image height in pixels; overrides the aspect ratio of the scene's camera
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    height: Option<u64>,
+
+    /// Samples per pixel
+    #[arg(short, long, value_parser = clap::value_parser!(u64).range(1..))]
+    samples: Option<u64>,
+
+    /// Maximum number of ray bounces
+    #[arg(short = 'b', long)]
+    max_bounces: Option<u64>,
+
+    /// Number of bounces after which paths may be ended early at random (Russian roulette)
+    #[arg(long)]
+    roulette_depth: Option<u64>,
+
+    /// Number of render threads (defaults to one per CPU)
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// Seed for the sampler and the random parts of built-in scenes; the same seed
+    /// always renders the same image
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Side of the square tiles the image is split into for rendering
+    #[arg(long)]
+    tile_size: Option<usize>,
+
+    /// Order in which tiles are rendered: scanline, spiral or hilbert
+    #[arg(long, value_parser = parse_tile_order)]
+    tile_order: Option<TileOrder>,
+
+    /// What to render: the full image, or a debug view of the geometry
+    #[arg(long, value_enum, default_value_t = IntegratorKind::Path)]
+    integrator: IntegratorKind,
+
+    /// Also render auxiliary images of the first hits, as a comma separated list of
+    /// normal, position, depth, albedo, material_id and object_id, or all; each is saved as
+    /// a PFM file named after the output, e.g. image.depth.pfm
+    #[arg(long, value_parser = parse_aovs)]
+    aovs: Option<AovList>,
+
+    /// Render progressively, adding this many samples per pixel to the whole image at a
+    /// time and saving a snapshot to the output file after each pass
+    #[arg(long)]
+    pass_samples: Option<u64>,
+
+    /// With --pass-samples, save snapshots at most this often (in seconds) instead of
+    /// after every pass
+    #[arg(long, requires = "pass_samples", value_parser = parse_seconds)]
+    snapshot_interval: Option<Duration>,
+
+    /// Stop sampling a pixel once the relative standard error of its brightness is below
+    /// this (e.g. 0.01); --samples then becomes the maximum per pixel
+    #[arg(long)]
+    adaptive_threshold: Option<f64>,
+
+    /// With --adaptive-threshold, the number of samples every pixel gets at least
+    #[arg(long, default_value_t = 16, requires = "adaptive_threshold")]
+    min_samples: u64,
+
+    /// Also save an image of how many samples each pixel got, from blue (fewest) to red
+    #[arg(long)]
+    heat_map: Option<PathBuf>,
+
+    /// Do not print the progress bar and render information
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+fn parse_format(name: &str) -> Result<ImageFormat, String> {
+    ImageFormat::from_name(name)
+        .ok_or_else(|| format!("unknown format `{}`, use png, png16, ppm or pfm", name))
+}
+
+fn parse_seconds(seconds: &str) -> Result<Duration, String> {
+    seconds
+        .parse::<f64>()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| format!("`{}` is not a number of seconds", seconds))
+}
+
+// A whole `--aovs` list, so that clap does not take it for a multi-valued option.
+#[derive(Clone)]
+struct AovList(Vec<Aov>);
+
+fn parse_aovs(names: &str) -> Result<AovList, String> {
+    if names == "all" {
+        return Ok(AovList(Aov::ALL.to_vec()));
+    }
+    names
+        .split(',')
+        .map(|name| {
+            Aov::from_name(name.trim()).ok_or_else(|| {
+                format!(
+                    "unknown AOV `{}`, use normal, position, depth, albedo, material_id, object_id or all",
+                    name
+                )
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(AovList)
+}
+
+// AOVs are saved next to the image, as `<stem>.<aov>.pfm`.
+fn aov_path(output: &Path, aov: &Aov) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    output.with_file_name(format!("{}.{}.pfm", stem, aov.name()))
+}
+
+fn parse_tile_order(name: &str) -> Result<TileOrder, String> {
+    TileOrder::from_name(name).ok_or_else(|| {
+        format!(
+            "unknown tile order `{}`, use scanline, spiral or hilbert",
+            name
+        )
+    })
+}
+
+fn apply_overrides(cli: &Cli, cam: &mut Camera) {
+    if let Some(width) = cli.width {
+        cam.image_width = width;
+    }
+    if let Some(height) = cli.height {
+        cam.fixed_image_height = Some(height);
+    }
+    if let Some(samples) = cli.samples {
+        cam.samples_per_pixel = samples;
+    }
+    if let Some(max_bounces) = cli.max_bounces {
+        cam.max_ray_bounces = max_bounces;
+    }
+    if let Some(roulette_depth) = cli.roulette_depth {
+        cam.roulette_depth = roulette_depth;
+    }
+    if let Some(seed) = cli.seed {
+        cam.seed = seed;
+    }
+    if let Some(tile_size) = cli.tile_size {
+        cam.tile_size = tile_size;
+    }
+    if let Some(tile_order) = cli.tile_order {
+        cam.tile_order = tile_order;
+    }
+    if let Some(threshold) = cli.adaptive_threshold {
+        cam.adaptive = Some(AdaptiveSampling {
+            threshold,
+            min_samples: cli.min_samples,
+        });
+    }
+    if let Some(pass_samples) = cli.pass_samples {
+        cam.samples_per_pass = pass_samples;
+    }
+    cam.integrator = cli.integrator.build(cam);
+    if let Some(AovList(aovs)) = &cli.aovs {
+        cam.aovs.clone_from(aovs);
+    }
+    cam.show_progress = !cli.quiet;
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    if let Some(threads) = cli.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|error| format!("cannot set up {} render threads: {}", threads, error))?;
+    }
+
+    // Checked before rendering, so a bad output name does not waste a whole render.
+    let format = match cli.format {
+        Some(format) => format,
+        None => ImageFormat::from_path(&cli.output).ok_or_else(|| {
+            format!(
+                "cannot guess the image format of {}, use .png, .ppm or .pfm or pass --format",
+                cli.output.display()
+            )
+        })?,
+    };
+    let heat_map_format = match &cli.heat_map {
+        Some(path) => Some(ImageFormat::from_path(path).ok_or_else(|| {
+            format!(
+                "cannot guess the image format of {}, use .png, .ppm or .pfm",
+                path.display()
+            )
+        })?),
+        None => None,
+    };
+
+    let (mut cam, world) = match &cli.scene {
+        Some(path) => {
+            let scene = load_scene(path).map_err(|error| error.to_string())?;
+            (scene.camera, scene.world)
+        }
+        None => match cli.builtin {
+            BuiltinScene::Spheres => (make_small_camera(), make_world()),
+            BuiltinScene::Big => {
+                let mut rng = Pcg64Mcg::seed_from_u64(cli.seed.unwrap_or(0));
+                (make_big_camera(), make_big_render(&mut rng))
+            }
+        },
+    };
+    apply_overrides(&cli, &mut cam);
+
+    let world = BvhNode::new(world);
+    let interval = cli.snapshot_interval.unwrap_or(Duration::ZERO);
+    let mut last_snapshot = Instant::now();
+    let samples_per_pixel = cam.samples_per_pixel;
+    let RenderOutput { image, aovs } = cam.render_progressive(&world, |image, samples_done| {
+        // The final image is saved below, after the last pass.
+        if cli.pass_samples.is_none()
+            || samples_done >= samples_per_pixel
+            || last_snapshot.elapsed() < interval
+        {
+            return;
+        }
+        if let Err(error) = save_image_as(image, &cli.output, format) {
+            eprintln!(
+                "warning: cannot save a snapshot to {}: {}",
+                cli.output.display(),
+                error
+            );
+        }
+        last_snapshot = Instant::now();
+    });
+
+    save_image_as(&image, &cli.output, format)
+        .map_err(|error| format!("cannot save {}: {}", cli.output.display(), error))?;
+
+    if !cli.quiet {
+        eprintln!("Saved the image to {}", cli.output.display());
+    }
+
+    for (aov, aov_image) in &aovs {
+        let path = aov_path(&cli.output, aov);
+        save_image_as(aov_image, &path, ImageFormat::Pfm)
+            .map_err(|error| format!("cannot save {}: {}", path.display(), error))?;
+        if !cli.quiet {
+            eprintln!("Saved the {} AOV to {}", aov.name(), path.display());
+        }
+    }
+
+    if let (Some(path), Some(format)) = (&cli.heat_map, heat_map_format) {
+        save_image_as(&image.sample_heat_map(), path, format)
+            .map_err(|error| format!("cannot save {}: {}", path.display(), error))?;
+        if !cli.quiet {
+            eprintln!(
+                "Saved the sample heat map to {}, {:.1} samples per pixel on average",
+                path.display(),
+                image.total_samples() as f64 / (image.width() * image.height()) as f64
+            );
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -1,7 +1,10 @@
+use std::sync::Arc;
+
 use crate::{
     color::Color,
     hittables::hittable::HitRecord,
-    my_math::prelude::{random_double, Ray, Vec3},
+    my_math::prelude::{random_double, Onb, Ray, Vec3, PI},
+    texture::texture::{SolidColor, Texture},
 };
 
 pub enum ScatterResult {
@@ -9,42 +12,160 @@ pub enum ScatterResult {
     Consume,
 }
 
-pub trait Material {
-    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> ScatterResult;
+// What kind of distribution a scattered direction was drawn from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BsdfFlags {
+    // The direction was the only one possible, as for a perfect mirror or glass. `eval`
+    // and `pdf` are zero for it, so it can only be found by sampling.
+    pub specular: bool,
+    // The direction goes through the surface rather than back from it.
+    pub transmission: bool,
+}
+
+impl BsdfFlags {
+    pub const REFLECTION: BsdfFlags = BsdfFlags {
+        specular: false,
+        transmission: false,
+    };
+    pub const SPECULAR_REFLECTION: BsdfFlags = BsdfFlags {
+        specular: true,
+        transmission: false,
+    };
+    pub const SPECULAR_TRANSMISSION: BsdfFlags = BsdfFlags {
+        specular: true,
+        transmission: true,
+    };
+}
+
+// A direction picked by `Material::sample`.
+pub struct BsdfSample {
+    // Direction the light arrives from, pointing away from the surface.
+    pub direction: Vec3,
+    // The BSDF times the cosine term for `direction`, like `Material::eval`. For specular
+    // samples, the fraction of light scattered times `pdf`.
+    pub value: Color,
+    // Density of `direction` over solid angle. For specular samples, the probability of
+    // choosing that one direction among the possible ones.
+    pub pdf: f64,
+    pub flags: BsdfFlags,
+}
+
+impl BsdfSample {
+    // Factor by which the sample scales the light it brings in, for a Monte Carlo estimate.
+    pub fn weight(&self) -> Color {
+        if self.pdf > 0. {
+            self.value / self.pdf
+        } else {
+            Color::new(0., 0., 0.)
+        }
+    }
+}
+
+// Directions are unit vectors pointing away from the hit point: `wo` towards where the
+// light goes (back along the incoming ray), `wi` towards where it comes from.
+pub trait Material: Send + Sync {
+    // The BSDF times the cosine term, |cos| of the angle between `wi` and the normal, so
+    // that it is the fraction of light from `wi` scattered towards `wo` per unit solid
+    // angle. Zero for specular materials.
+    fn eval(&self, _hit_record: &HitRecord, _wo: &Vec3, _wi: &Vec3) -> Color {
+        Color::new(0., 0., 0.)
+    }
+
+    // Picks a direction for light to come from, importance sampled from the BSDF. None
+    // when the light is absorbed.
+    fn sample(&self, hit_record: &HitRecord, wo: &Vec3) -> Option<BsdfSample>;
+
+    // Density over solid angle with which `sample` picks `wi`. Zero for specular materials.
+    fn pdf(&self, _hit_record: &HitRecord, _wo: &Vec3, _wi: &Vec3) -> f64 {
+        0.
+    }
+
+    // The ray continuing the path and the factor to scale its light by.
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> ScatterResult {
+        let wo = -ray.direction.normalized();
+        match self.sample(hit_record, &wo) {
+            Some(sample) => ScatterResult::Scatter {
+                ray: Ray::new(hit_record.intersection_point, sample.direction, ray.time),
+                attenuation: sample.weight(),
+            },
+            None => ScatterResult::Consume,
+        }
+    }
+
+    // Light given off by the surface itself. Most materials do not glow at all.
+    fn emitted(&self, _hit_record: &HitRecord) -> Color {
+        Color::new(0., 0., 0.)
+    }
+
+    // Whether `emitted` can be anything but black, so that the surface is a light.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+}
+
+fn albedo_at(texture: &Arc<dyn Texture>, hit_record: &HitRecord) -> Color {
+    texture.value(hit_record.u, hit_record.v, &hit_record.intersection_point)
 }
 
 pub struct Lambertian {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(color: Color) -> Self {
-        Lambertian { albedo: color }
+        Lambertian::from_texture(Arc::new(SolidColor::new(color)))
+    }
+    pub fn from_texture(texture: Arc<dyn Texture>) -> Self {
+        Lambertian { albedo: texture }
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _: &Ray, hit_record: &HitRecord) -> ScatterResult {
-        let mut scatter_direction = hit_record.normal + Vec3::random_unit_vector();
+    fn eval(&self, hit_record: &HitRecord, _: &Vec3, wi: &Vec3) -> Color {
+        albedo_at(&self.albedo, hit_record) * Lambertian::pdf_cosine(hit_record, wi)
+    }
 
-        if scatter_direction.near_zero() {
-            scatter_direction = hit_record.normal;
+    fn sample(&self, hit_record: &HitRecord, _: &Vec3) -> Option<BsdfSample> {
+        // Normal + a random unit vector is cosine distributed.
+        let mut direction = hit_record.normal + Vec3::random_unit_vector();
+        if direction.near_zero() {
+            direction = hit_record.normal;
         }
+        let direction = direction.normalized();
 
-        ScatterResult::Scatter {
-            ray: Ray::new(hit_record.intersection_point, scatter_direction),
-            attenuation: self.albedo,
-        }
+        let pdf = Lambertian::pdf_cosine(hit_record, &direction);
+        Some(BsdfSample {
+            direction,
+            value: albedo_at(&self.albedo, hit_record) * pdf,
+            pdf,
+            flags: BsdfFlags::REFLECTION,
+        })
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, _: &Vec3, wi: &Vec3) -> f64 {
+        Lambertian::pdf_cosine(hit_record, wi)
+    }
+}
+
+impl Lambertian {
+    // cos(theta) / pi, which is both the BSDF times the cosine term (up to the albedo) and
+    // the density of cosine-weighted sampling.
+    fn pdf_cosine(hit_record: &HitRecord, direction: &Vec3) -> f64 {
+        let cosine = hit_record.normal.dot(direction);
+        cosine.max(0.) / PI
     }
 }
 
 pub struct Metal {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
     fuzz: f64,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Metal::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+    pub fn from_texture(albedo: Arc<dyn Texture>, fuzz: f64) -> Self {
         Self {
             albedo,
             fuzz: if fuzz < 1. { fuzz } else { 1. },
@@ -52,28 +173,84 @@ impl Metal {
     }
 }
 
+impl Metal {
+    // Density of the directions `sample` picks for a glossy metal, which are the mirror
+    // direction moved to a uniformly random point in a ball with radius `fuzz` around it.
+    // A ray along `wi` crosses that ball between t = b - s and t = b + s, and the density
+    // is the volume of the thin cone along the ray within the ball, the integral of t^2
+    // over the chord, divided by the volume of the ball, 4/3 pi fuzz^3. Unlike points on
+    // the sphere's surface, this stays finite at the edge of the lobe, which keeps shadow
+    // rays to point lights from producing fireflies.
+    fn glossy_pdf(&self, hit_record: &HitRecord, wo: &Vec3, wi: &Vec3) -> f64 {
+        if wi.dot(&hit_record.normal) <= 0. {
+            // `sample` absorbs the light instead of picking these.
+            return 0.;
+        }
+        let reflected = (-*wo).reflect(&hit_record.normal);
+        let b = wi.dot(&reflected);
+        let discriminant = b * b - (1. - self.fuzz * self.fuzz);
+        if b <= 0. || discriminant <= 0. {
+            return 0.;
+        }
+        let s = discriminant.sqrt();
+        s * (3. * b * b + s * s) / (2. * PI * self.fuzz.powi(3))
+    }
+}
+
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> ScatterResult {
-        let reflected = ray.direction.normalized().reflect(&hit_record.normal);
-        let direction = reflected + Vec3::random_unit_vector() * self.fuzz;
-        if direction.dot(&hit_record.normal) < 0. {
-            ScatterResult::Consume
+    fn eval(&self, hit_record: &HitRecord, wo: &Vec3, wi: &Vec3) -> Color {
+        if self.fuzz == 0. {
+            return Color::new(0., 0., 0.);
+        }
+        // Every direction that is not absorbed reflects all of the albedo, so the BSDF
+        // times the cosine is the albedo times the density.
+        albedo_at(&self.albedo, hit_record) * self.glossy_pdf(hit_record, wo, wi)
+    }
+
+    fn sample(&self, hit_record: &HitRecord, wo: &Vec3) -> Option<BsdfSample> {
+        let reflected = (-*wo).reflect(&hit_record.normal);
+        let albedo = albedo_at(&self.albedo, hit_record);
+        if self.fuzz == 0. {
+            return Some(BsdfSample {
+                direction: reflected,
+                value: albedo,
+                pdf: 1.,
+                flags: BsdfFlags::SPECULAR_REFLECTION,
+            });
+        }
+
+        let direction = (reflected + Vec3::random_in_unit_sphere() * self.fuzz).normalized();
+        if direction.dot(&hit_record.normal) <= 0. {
+            return None;
+        }
+        let pdf = self.glossy_pdf(hit_record, wo, &direction);
+        Some(BsdfSample {
+            direction,
+            value: albedo * pdf,
+            pdf,
+            flags: BsdfFlags::REFLECTION,
+        })
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, wo: &Vec3, wi: &Vec3) -> f64 {
+        if self.fuzz == 0. {
+            0.
         } else {
-            ScatterResult::Scatter {
-                ray: Ray::new(hit_record.intersection_point, direction),
-                attenuation: self.albedo,
-            }
+            self.glossy_pdf(hit_record, wo, wi)
         }
     }
 }
 
 pub struct Dielectric {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
     refractive_index: f64,
 }
 
 impl Dielectric {
     pub fn new(albedo: Color, refractive_index: f64) -> Self {
+        Dielectric::from_texture(Arc::new(SolidColor::new(albedo)), refractive_index)
+    }
+    pub fn from_texture(albedo: Arc<dyn Texture>, refractive_index: f64) -> Self {
         Self {
             albedo,
             refractive_index,
@@ -86,8 +263,11 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> ScatterResult {
-        let normalized_direction = ray.direction.normalized();
+    // Reflects or refracts at random, in proportion to the Fresnel reflectance. The
+    // probability of the chosen branch is both its pdf and its share of the light, which
+    // leaves the albedo as the weight of either.
+    fn sample(&self, hit_record: &HitRecord, wo: &Vec3) -> Option<BsdfSample> {
+        let normalized_direction = -*wo;
 
         let cos_theta = -normalized_direction.dot(&hit_record.normal).min(1.);
         let sin_theta = (1. - cos_theta.powi(2)).sqrt();
@@ -98,20 +278,166 @@ impl Material for Dielectric {
             self.refractive_index
         };
 
-        let should_reflect = refraction_fraction * sin_theta > 1.;
-        let reflectance_schlick =
-            Dielectric::reflectance(cos_theta, self.refractive_index) > random_double();
+        let albedo = albedo_at(&self.albedo, hit_record) * 0.96;
+        if refraction_fraction * sin_theta > 1. {
+            // Total internal reflection.
+            return Some(BsdfSample {
+                direction: normalized_direction.reflect(&hit_record.normal),
+                value: albedo,
+                pdf: 1.,
+                flags: BsdfFlags::SPECULAR_REFLECTION,
+            });
+        }
 
-        let direction = if should_reflect || reflectance_schlick {
-            // Here we are **REFLECTING**, not refracting.
-            normalized_direction.reflect(&hit_record.normal)
+        let reflectance = Dielectric::reflectance(cos_theta, self.refractive_index);
+        if reflectance > random_double() {
+            Some(BsdfSample {
+                direction: normalized_direction.reflect(&hit_record.normal),
+                value: albedo * reflectance,
+                pdf: reflectance,
+                flags: BsdfFlags::SPECULAR_REFLECTION,
+            })
         } else {
-            normalized_direction.refract(&hit_record.normal, refraction_fraction)
-        };
+            Some(BsdfSample {
+                direction: normalized_direction
+                    .refract(&hit_record.normal, refraction_fraction)
+                    .normalized(),
+                value: albedo * (1. - reflectance),
+                pdf: 1. - reflectance,
+                flags: BsdfFlags::SPECULAR_TRANSMISSION,
+            })
+        }
+    }
+}
+
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        DiffuseLight::from_texture(Arc::new(SolidColor::new(emit)))
+    }
+    pub fn from_texture(emit: Arc<dyn Texture>) -> Self {
+        DiffuseLight { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn sample(&self, _: &HitRecord, _: &Vec3) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emitted(&self, hit_record: &HitRecord) -> Color {
+        albedo_at(&self.emit, hit_record)
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
+
+// Phase functions scatter light inside participating media such as `ConstantMedium`.
+// There is no surface there, so they ignore the normal and have no cosine term: `eval`
+// is the albedo times the density of light from `wi` going on towards `wo`.
+
+// Scatters light equally in all directions.
+pub struct Isotropic {
+    albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Isotropic::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn eval(&self, hit_record: &HitRecord, _: &Vec3, _: &Vec3) -> Color {
+        albedo_at(&self.albedo, hit_record) / (4. * PI)
+    }
 
-        ScatterResult::Scatter {
-            ray: Ray::new(hit_record.intersection_point, direction),
-            attenuation: self.albedo * 0.96,
+    fn sample(&self, hit_record: &HitRecord, _: &Vec3) -> Option<BsdfSample> {
+        let pdf = 1. / (4. * PI);
+        Some(BsdfSample {
+            direction: Vec3::random_unit_vector(),
+            value: albedo_at(&self.albedo, hit_record) * pdf,
+            pdf,
+            flags: BsdfFlags::REFLECTION,
+        })
+    }
+
+    fn pdf(&self, _: &HitRecord, _: &Vec3, _: &Vec3) -> f64 {
+        1. / (4. * PI)
+    }
+}
+
+// The Henyey-Greenstein phase function. `g`, in (-1, 1), is the average cosine of the
+// angle light turns by: positive values scatter mostly forward, like fog and clouds do,
+// negative ones mostly back, and zero is isotropic.
+pub struct HenyeyGreenstein {
+    albedo: Arc<dyn Texture>,
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> Self {
+        HenyeyGreenstein::from_texture(Arc::new(SolidColor::new(albedo)), g)
+    }
+    pub fn from_texture(albedo: Arc<dyn Texture>, g: f64) -> Self {
+        HenyeyGreenstein {
+            albedo,
+            // At 1 or -1 the lobe collapses to a single direction.
+            g: g.clamp(-0.99, 0.99),
         }
     }
+
+    // Light travels along -wi before and along wo after scattering, so the angle it
+    // turns by has the cosine -wi . wo.
+    fn phase(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        let g = self.g;
+        let denominator = 1. + g * g + 2. * g * wi.dot(wo);
+        (1. - g * g) / (4. * PI * denominator * denominator.sqrt())
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn eval(&self, hit_record: &HitRecord, wo: &Vec3, wi: &Vec3) -> Color {
+        albedo_at(&self.albedo, hit_record) * self.phase(wo, wi)
+    }
+
+    fn sample(&self, hit_record: &HitRecord, wo: &Vec3) -> Option<BsdfSample> {
+        // The cosine of the turning angle, by inverting the cumulative distribution.
+        let g = self.g;
+        let xi = random_double();
+        let cos_theta = if g.abs() < 1e-3 {
+            1. - 2. * xi
+        } else {
+            let ratio = (1. - g * g) / (1. - g + 2. * g * xi);
+            ((1. + g * g - ratio * ratio) / (2. * g)).clamp(-1., 1.)
+        };
+        let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+        let phi = 2. * PI * random_double();
+        // The path is traced backwards: wi turns by the angle away from -wo.
+        let direction = Onb::new(&-*wo).local(&Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+
+        let pdf = self.phase(wo, &direction);
+        Some(BsdfSample {
+            direction,
+            value: albedo_at(&self.albedo, hit_record) * pdf,
+            pdf,
+            flags: BsdfFlags::REFLECTION,
+        })
+    }
+
+    fn pdf(&self, _: &HitRecord, wo: &Vec3, wi: &Vec3) -> f64 {
+        self.phase(wo, wi)
+    }
 }
@@ -1,2 +1,2 @@
-pub const INFINITY: f64 = std::f64::INFINITY;
-pub const PI: f64 = 3.141592653589793285;
+pub const INFINITY: f64 = f64::INFINITY;
+pub const PI: f64 = std::f64::consts::PI;
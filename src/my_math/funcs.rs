@@ -1,4 +1,7 @@
-use rand::{distributions::Uniform, Rng};
+use std::cell::RefCell;
+
+use rand::{distributions::Uniform, Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
 
 use super::constants::PI;
 
@@ -6,11 +9,37 @@ pub fn deg2rad(degrees: f64) -> f64 {
     degrees / 360.0 * 2.0 * PI
 }
 
+thread_local! {
+    // Every sampling function draws from this generator. The renderer re-seeds it for
+    // every sample, so the result does not depend on which thread renders what.
+    static RNG: RefCell<Pcg64Mcg> = RefCell::new(Pcg64Mcg::from_entropy());
+}
+
+// Restarts the random sequence of the current thread.
+pub fn seed_thread_rng(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = Pcg64Mcg::seed_from_u64(seed));
+}
+
+// Combines a base seed with e.g. pixel coordinates and a sample index into the seed of
+// an independent stream. Neighbouring inputs give unrelated outputs.
+pub fn derive_seed(seed: u64, values: &[u64]) -> u64 {
+    values.iter().fold(splitmix64(seed), |hash, &value| {
+        splitmix64(hash ^ value.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    })
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
 pub fn random_double() -> f64 {
     random_double_range(0., 1.)
 }
 
 pub fn random_double_range(min: f64, max: f64) -> f64 {
-    let rng = Uniform::new(min, max);
-    rand::thread_rng().sample(rng)
+    let distribution = Uniform::new(min, max);
+    RNG.with(|rng| rng.borrow_mut().sample(distribution))
 }
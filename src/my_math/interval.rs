@@ -1,5 +1,6 @@
 use super::constants::INFINITY;
 
+#[derive(Debug, Clone, Copy)]
 pub struct Interval {
     pub min: f64,
     pub max: f64,
@@ -9,12 +10,26 @@ impl Interval {
     pub const fn new(min: f64, max: f64) -> Self {
         Interval { min, max }
     }
+    pub fn enclosing(a: &Interval, b: &Interval) -> Self {
+        // The tightest interval containing both `a` and `b`.
+        Interval {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
     pub fn contains(&self, x: f64) -> bool {
         self.min <= x && x <= self.max
     }
     pub fn surrounds(&self, x: f64) -> bool {
         self.min < x && x < self.max
     }
+    pub fn size(&self) -> f64 {
+        self.max - self.min
+    }
+    pub fn expand(&self, delta: f64) -> Self {
+        let padding = delta / 2.;
+        Interval::new(self.min - padding, self.max + padding)
+    }
 
     pub fn clamp(&self, x: f64) -> f64 {
         x.clamp(self.min, self.max)
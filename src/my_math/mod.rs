@@ -4,3 +4,6 @@ pub mod ray;
 pub mod constants;
 pub mod funcs;
 pub mod interval;
+pub mod onb;
+pub mod roots;
+pub mod transform;
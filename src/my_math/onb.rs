@@ -0,0 +1,35 @@
+use super::vec3::Vec3;
+
+// Orthonormal basis around a given direction `w`, used to turn directions sampled around
+// the Z axis into world space.
+#[derive(Debug, Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn new(w: &Vec3) -> Self {
+        let w = w.normalized();
+        // Any vector that is not parallel to `w` will do to start the cross products.
+        let helper = if w.x.abs() > 0.9 {
+            Vec3::new(0., 1., 0.)
+        } else {
+            Vec3::new(1., 0., 0.)
+        };
+        let v = w.cross(&helper).normalized();
+        let u = w.cross(&v);
+        Onb { u, v, w }
+    }
+
+    // The world space vector with coordinates `a` in this basis.
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        self.u * a.x + self.v * a.y + self.w * a.z
+    }
+
+    // The coordinates in this basis of the world space vector `a`, the inverse of `local`.
+    pub fn coordinates(&self, a: &Vec3) -> Vec3 {
+        Vec3::new(a.dot(&self.u), a.dot(&self.v), a.dot(&self.w))
+    }
+}
@@ -1,4 +1,4 @@
 
-pub use super::{vec3::*, ray::*, constants::*, funcs::*, interval::*};
+pub use super::{vec3::*, ray::*, constants::*, funcs::*, interval::*, onb::*, roots::*, transform::*};
 
 pub type Point3 = Vec3;
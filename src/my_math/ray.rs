@@ -1,17 +1,20 @@
 use super::prelude::*;
 
-
 #[derive(Clone, Copy)]
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    // When the ray is traced, within the camera's shutter interval. Moving objects are
+    // intersected where they are at this time, and rays spawned from a hit keep it.
+    pub time: f64,
 }
 
 impl Ray {
-    pub fn new(origin: Point3, direction: Vec3) -> Self {
+    pub fn new(origin: Point3, direction: Vec3, time: f64) -> Self {
         Ray {
-            origin: origin,
-            direction: direction,
+            origin,
+            direction,
+            time,
         }
     }
     pub fn at(self, at: f64) -> Point3 {
@@ -0,0 +1,263 @@
+use super::constants::PI;
+
+// Real roots of low degree polynomials, following Jochen Schwarze's "Cubic and Quartic
+// Roots" from Graphics Gems. Coefficients are given from the constant term up, and the
+// roots come back in no particular order. Repeated roots may be reported once.
+
+// Tolerance for the polynomials made monic and scaled by `normalize`, where the largest
+// root is about one.
+const EPSILON: f64 = 1e-9;
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < EPSILON
+}
+
+// Divides the polynomial by its leading coefficient and substitutes x = scale y, with the
+// scale picked so that no coefficient of the polynomial in y is bigger than one. The
+// roots in y are then at most about one, whatever the units of x, which is what makes a
+// fixed `EPSILON` work. Returns the scale and the coefficients in y.
+fn normalize<const N: usize>(coefficients: [f64; N]) -> (f64, [f64; N]) {
+    let degree = N - 1;
+    let leading = coefficients[degree];
+    let scale = (0..degree)
+        .map(|power| {
+            (coefficients[power] / leading)
+                .abs()
+                .powf(1. / (degree - power) as f64)
+        })
+        .fold(0., f64::max);
+    let scale = if scale > 0. { scale } else { 1. };
+    let mut normalized = [0.; N];
+    for (power, value) in normalized.iter_mut().enumerate() {
+        *value = coefficients[power] / leading / scale.powi((degree - power) as i32);
+    }
+    (scale, normalized)
+}
+
+// c[2] x^2 + c[1] x + c[0] = 0
+pub fn solve_quadratic(coefficients: [f64; 3]) -> Vec<f64> {
+    let (scale, [c0, c1, _]) = normalize(coefficients);
+    let p = c1 / 2.;
+    let q = c0;
+    let discriminant = p * p - q;
+    let roots = if is_zero(discriminant) {
+        vec![-p]
+    } else if discriminant < 0. {
+        Vec::new()
+    } else {
+        let sqrt_discriminant = discriminant.sqrt();
+        vec![sqrt_discriminant - p, -sqrt_discriminant - p]
+    };
+    roots.into_iter().map(|root| root * scale).collect()
+}
+
+// c[3] x^3 + c[2] x^2 + c[1] x + c[0] = 0
+pub fn solve_cubic(coefficients: [f64; 4]) -> Vec<f64> {
+    // Normal form x^3 + a x^2 + b x + c, then x = y - a/3 gives y^3 + 3p y + 2q.
+    let (scale, [c, b, a, _]) = normalize(coefficients);
+
+    let a_squared = a * a;
+    let p = (-a_squared / 3. + b) / 3.;
+    let q = (2. / 27. * a * a_squared - a * b / 3. + c) / 2.;
+    let p_cubed = p * p * p;
+    let discriminant = q * q + p_cubed;
+
+    let mut roots = if is_zero(discriminant) {
+        if is_zero(q) {
+            vec![0.]
+        } else {
+            let u = (-q).cbrt();
+            vec![2. * u, -u]
+        }
+    } else if discriminant < 0. {
+        // Three real roots: the trigonometric solution.
+        let phi = (-q / (-p_cubed).sqrt()).clamp(-1., 1.).acos() / 3.;
+        let t = 2. * (-p).sqrt();
+        vec![
+            t * phi.cos(),
+            -t * (phi + PI / 3.).cos(),
+            -t * (phi - PI / 3.).cos(),
+        ]
+    } else {
+        // One real root: Cardano's formula.
+        let sqrt_discriminant = discriminant.sqrt();
+        vec![(sqrt_discriminant - q).cbrt() - (sqrt_discriminant + q).cbrt()]
+    };
+
+    for root in roots.iter_mut() {
+        *root = (*root - a / 3.) * scale;
+    }
+    roots
+}
+
+// c[4] x^4 + c[3] x^3 + c[2] x^2 + c[1] x + c[0] = 0, with Ferrari's method
+pub fn solve_quartic(coefficients: [f64; 5]) -> Vec<f64> {
+    // Normal form x^4 + a x^3 + b x^2 + c x + d, then x = y - a/4 gives
+    // y^4 + p y^2 + q y + r.
+    let (scale, [d, c, b, a, _]) = normalize(coefficients);
+
+    let a_squared = a * a;
+    let p = -3. / 8. * a_squared + b;
+    let q = a_squared * a / 8. - a * b / 2. + c;
+    let r = -3. / 256. * a_squared * a_squared + a_squared * b / 16. - a * c / 4. + d;
+
+    let mut roots = if is_zero(r) {
+        // y (y^3 + p y + q) = 0
+        let mut roots = solve_cubic([q, p, 0., 1.]);
+        roots.push(0.);
+        roots
+    } else {
+        // A root z of the resolvent cubic 4 (2z - p)(z^2 - r) = q^2 splits the quartic
+        // into two quadratics. The cubic is negative at z = p/2, so its largest root makes
+        // both factors non-negative. Where rounding makes one slightly negative it is
+        // taken as zero, rather than losing the roots of grazing rays.
+        let z = solve_cubic([r * p / 2. - q * q / 8., -r, -p / 2., 1.])
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        let u = (z * z - r).max(0.).sqrt();
+        let v = (2. * z - p).max(0.).sqrt();
+        let v = if q < 0. { -v } else { v };
+        let mut roots = solve_quadratic([z - u, v, 1.]);
+        roots.extend(solve_quadratic([z + u, -v, 1.]));
+        roots
+    };
+
+    for root in roots.iter_mut() {
+        *root = (*root - a / 4.) * scale;
+    }
+    roots
+}
+
+// Value of the polynomial with coefficients `c` at `x`.
+pub fn evaluate_polynomial(c: &[f64], x: f64) -> f64 {
+    c.iter()
+        .rev()
+        .fold(0., |value, coefficient| value * x + coefficient)
+}
+
+// Improves an approximate root of the polynomial with a few Newton steps. Near a double
+// root the slope is almost flat and a step can shoot far off, so steps are only taken
+// while they bring the polynomial closer to zero.
+pub fn polish_root(c: &[f64], mut x: f64) -> f64 {
+    let derivative: Vec<f64> = c
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(power, coefficient)| power as f64 * coefficient)
+        .collect();
+    let mut value = evaluate_polynomial(c, x);
+    for _ in 0..2 {
+        let slope = evaluate_polynomial(&derivative, x);
+        if slope == 0. {
+            break;
+        }
+        let next = x - value / slope;
+        let next_value = evaluate_polynomial(c, next);
+        if next_value.abs() >= value.abs() {
+            break;
+        }
+        (x, value) = (next, next_value);
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-6 * a.abs().max(b.abs())
+    }
+
+    // Roots sorted, with repeated roots that were reported more than once merged.
+    fn sorted(mut roots: Vec<f64>) -> Vec<f64> {
+        roots.sort_by(f64::total_cmp);
+        roots.dedup_by(|a, b| close(*a, *b));
+        roots
+    }
+
+    fn assert_roots(roots: Vec<f64>, expected: &[f64]) {
+        let roots = sorted(roots);
+        assert_eq!(roots.len(), expected.len(), "roots {:?}", roots);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!(
+                close(*root, *expected),
+                "roots {:?}, expected {:?}",
+                roots,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn quadratic() {
+        // (x - 1)(x + 3)
+        assert_roots(solve_quadratic([-3., 2., 1.]), &[-3., 1.]);
+        // (x - 3)^2
+        assert_roots(solve_quadratic([9., -6., 1.]), &[3.]);
+        // x^2 + 1
+        assert_roots(solve_quadratic([1., 0., 1.]), &[]);
+    }
+
+    #[test]
+    fn cubic_with_three_real_roots() {
+        // 2 (x - 1)(x - 2)(x - 3)
+        assert_roots(solve_cubic([-12., 22., -12., 2.]), &[1., 2., 3.]);
+    }
+
+    #[test]
+    fn cubic_with_one_real_root() {
+        // (x - 2)(x^2 + 1)
+        assert_roots(solve_cubic([-2., 1., -2., 1.]), &[2.]);
+    }
+
+    #[test]
+    fn cubic_with_repeated_roots() {
+        // (x - 1)^2 (x + 2)
+        assert_roots(solve_cubic([2., -3., 0., 1.]), &[-2., 1.]);
+        // (x - 1)^3
+        assert_roots(solve_cubic([-1., 3., -3., 1.]), &[1.]);
+    }
+
+    #[test]
+    fn quartic_with_no_real_roots() {
+        // (x^2 + 1)(x^2 + 4)
+        assert_roots(solve_quartic([4., 0., 5., 0., 1.]), &[]);
+    }
+
+    #[test]
+    fn quartic_with_two_real_roots() {
+        // (x - 1)(x + 2)(x^2 + 1)
+        assert_roots(solve_quartic([-2., 1., -1., 1., 1.]), &[-2., 1.]);
+    }
+
+    #[test]
+    fn quartic_with_four_real_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(solve_quartic([24., -50., 35., -10., 1.]), &[1., 2., 3., 4.]);
+    }
+
+    #[test]
+    fn quartic_with_a_double_root() {
+        // (x - 1)^2 (x - 2)(x + 3)
+        assert_roots(solve_quartic([-6., 13., -7., -1., 1.]), &[-3., 1., 2.]);
+    }
+
+    #[test]
+    fn roots_do_not_depend_on_scale() {
+        for scale in [1e-6_f64, 1e-3, 1e3, 1e6] {
+            let expected = [scale, 2. * scale, 3. * scale, 4. * scale];
+            let coefficients = [
+                24. * scale.powi(4),
+                -50. * scale.powi(3),
+                35. * scale.powi(2),
+                -10. * scale,
+                1.,
+            ];
+            assert_roots(solve_quartic(coefficients), &expected);
+            // (x - scale)^2
+            assert_roots(solve_quadratic([scale * scale, -2. * scale, 1.]), &[scale]);
+        }
+    }
+}
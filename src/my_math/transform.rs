@@ -0,0 +1,314 @@
+use super::prelude::*;
+
+type Matrix = [[f64; 4]; 4];
+
+const IDENTITY: Matrix = [
+    [1., 0., 0., 0.],
+    [0., 1., 0., 0.],
+    [0., 0., 1., 0.],
+    [0., 0., 0., 1.],
+];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut product = [[0.; 4]; 4];
+    for (i, row) in product.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    product
+}
+
+// Affine transform as a 4x4 matrix acting on column vectors, kept together with its
+// inverse so that neither has to be computed while rendering. Transforms are built from
+// translations, rotations and scalings chained with `then`.
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    matrix: Matrix,
+    inverse: Matrix,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        matrix: IDENTITY,
+        inverse: IDENTITY,
+    };
+
+    pub fn translation(offset: Vec3) -> Self {
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for axis in 0..3 {
+            matrix[axis][3] = offset[axis];
+            inverse[axis][3] = -offset[axis];
+        }
+        Transform { matrix, inverse }
+    }
+
+    // Scaling by a factor per axis. None of them may be zero.
+    pub fn scaling(factors: Vec3) -> Self {
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for axis in 0..3 {
+            matrix[axis][axis] = factors[axis];
+            inverse[axis][axis] = 1. / factors[axis];
+        }
+        Transform { matrix, inverse }
+    }
+
+    // Counterclockwise rotation by `degrees` around `axis`, looking down the axis towards
+    // the origin.
+    pub fn rotation(axis: &Vec3, degrees: f64) -> Self {
+        let a = axis.normalized();
+        let (sin, cos) = deg2rad(degrees).sin_cos();
+        let k = 1. - cos;
+        // Rodrigues' rotation formula.
+        let rotation = [
+            [
+                cos + a.x * a.x * k,
+                a.x * a.y * k - a.z * sin,
+                a.x * a.z * k + a.y * sin,
+            ],
+            [
+                a.y * a.x * k + a.z * sin,
+                cos + a.y * a.y * k,
+                a.y * a.z * k - a.x * sin,
+            ],
+            [
+                a.z * a.x * k - a.y * sin,
+                a.z * a.y * k + a.x * sin,
+                cos + a.z * a.z * k,
+            ],
+        ];
+        // The inverse of a rotation is its transpose.
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for i in 0..3 {
+            for j in 0..3 {
+                matrix[i][j] = rotation[i][j];
+                inverse[j][i] = rotation[i][j];
+            }
+        }
+        Transform { matrix, inverse }
+    }
+
+    // This transform followed by `next`.
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform {
+            matrix: multiply(&next.matrix, &self.matrix),
+            inverse: multiply(&self.inverse, &next.inverse),
+        }
+    }
+
+    // This transform followed by a translation by `offset`. Same as `then` with a
+    // translation, without the matrix products.
+    pub fn translated(&self, offset: Vec3) -> Transform {
+        let moved_back = self.inverse().vector(&offset);
+        let mut result = *self;
+        for axis in 0..3 {
+            result.matrix[axis][3] += offset[axis];
+            result.inverse[axis][3] -= moved_back[axis];
+        }
+        result
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn point(&self, p: &Point3) -> Point3 {
+        self.vector(p) + Vec3::new(self.matrix[0][3], self.matrix[1][3], self.matrix[2][3])
+    }
+
+    // Directions and offsets ignore the translation.
+    pub fn vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.matrix;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    // Normals go through the inverse transpose to stay perpendicular to the transformed
+    // surface. The result is not normalized.
+    pub fn normal(&self, n: &Vec3) -> Vec3 {
+        let inv = &self.inverse;
+        Vec3::new(
+            inv[0][0] * n.x + inv[1][0] * n.y + inv[2][0] * n.z,
+            inv[0][1] * n.x + inv[1][1] * n.y + inv[2][1] * n.z,
+            inv[0][2] * n.x + inv[1][2] * n.y + inv[2][2] * n.z,
+        )
+    }
+
+    // The ray with both origin and direction transformed. The direction keeps its scale,
+    // so the ray parameter `t` of a point is the same on both sides.
+    pub fn ray(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            self.point(&ray.origin),
+            self.vector(&ray.direction),
+            ray.time,
+        )
+    }
+
+    // Determinant of the linear part, the factor by which volumes are scaled.
+    pub fn determinant(&self) -> f64 {
+        let m = &self.matrix;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
+
+// A transform given by its parts, applied in this order: scaling by a factor per axis,
+// rotation by angles in degrees around the x, y and z axes, in that order, and
+// translation. Unlike matrices, parts can be interpolated, which animates the transform.
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    pub scale: Vec3,
+    pub rotation: Vec3,
+    pub translation: Vec3,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement {
+            scale: Vec3::new(1., 1., 1.),
+            rotation: Vec3::new(0., 0., 0.),
+            translation: Vec3::new(0., 0., 0.),
+        }
+    }
+}
+
+impl Placement {
+    pub fn transform(&self) -> Transform {
+        Transform::scaling(self.scale)
+            .then(&Transform::rotation(
+                &Vec3::new(1., 0., 0.),
+                self.rotation.x,
+            ))
+            .then(&Transform::rotation(
+                &Vec3::new(0., 1., 0.),
+                self.rotation.y,
+            ))
+            .then(&Transform::rotation(
+                &Vec3::new(0., 0., 1.),
+                self.rotation.z,
+            ))
+            .then(&Transform::translation(self.translation))
+    }
+
+    // The placement a fraction `t` of the way from this one to `other`. Angles are
+    // interpolated as given, so going from 0 to 720 degrees spins twice.
+    pub fn lerp(&self, other: &Placement, t: f64) -> Placement {
+        Placement {
+            scale: self.scale + (other.scale - self.scale) * t,
+            rotation: self.rotation + (other.rotation - self.rotation) * t,
+            translation: self.translation + (other.translation - self.translation) * t,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Scaled unevenly, rotated around a skew axis and moved, so no part is trivial.
+    fn skewed() -> Transform {
+        Transform::scaling(Vec3::new(2., 0.5, -3.))
+            .then(&Transform::rotation(&Vec3::new(1., 2., 3.), 37.))
+            .then(&Transform::translation(Vec3::new(4., -5., 6.)))
+    }
+
+    fn assert_close(a: &Vec3, b: &Vec3) {
+        assert!((*a - *b).length() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn a_transform_followed_by_its_inverse_is_the_identity() {
+        let t = skewed();
+        for product in [t.then(&t.inverse()), t.inverse().then(&t)] {
+            for (row, identity_row) in product.matrix.iter().zip(IDENTITY.iter()) {
+                for (value, expected) in row.iter().zip(identity_row) {
+                    assert!((value - expected).abs() < 1e-12, "{:?}", product.matrix);
+                }
+            }
+        }
+        let p = Point3::new(0.3, -1.7, 2.9);
+        assert_close(&t.inverse().point(&t.point(&p)), &p);
+        assert_close(&t.inverse().vector(&t.vector(&p)), &p);
+    }
+
+    #[test]
+    fn then_applies_the_first_transform_first() {
+        let scale = Transform::scaling(Vec3::new(2., 2., 2.));
+        let shift = Transform::translation(Vec3::new(1., 0., 0.));
+        let p = Point3::new(1., 1., 1.);
+        assert_close(&scale.then(&shift).point(&p), &Point3::new(3., 2., 2.));
+        assert_close(&shift.then(&scale).point(&p), &Point3::new(4., 2., 2.));
+    }
+
+    #[test]
+    fn translated_is_then_with_a_translation() {
+        let offset = Vec3::new(-1., 2.5, 0.25);
+        let expected = skewed().then(&Transform::translation(offset));
+        let translated = skewed().translated(offset);
+        for (p, q) in [
+            (translated.matrix, expected.matrix),
+            (translated.inverse, expected.inverse),
+        ] {
+            for (row, expected_row) in p.iter().zip(q.iter()) {
+                for (value, expected) in row.iter().zip(expected_row) {
+                    assert!((value - expected).abs() < 1e-12, "{:?} != {:?}", p, q);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scaling() {
+        let t = skewed();
+        // Pairs of a surface normal and a tangent perpendicular to it.
+        let normal = Vec3::new(1., 1., 0.).normalized();
+        for tangent in [
+            Vec3::new(1., -1., 0.),
+            Vec3::new(0., 0., 1.),
+            Vec3::new(2., -2., 5.),
+        ] {
+            let dot = t
+                .normal(&normal)
+                .normalized()
+                .dot(&t.vector(&tangent).normalized());
+            assert!(dot.abs() < 1e-12, "tangent {:?} is off by {}", tangent, dot);
+        }
+        // Plain vector transformation would not have kept it perpendicular.
+        let wrong = t.vector(&normal).normalized();
+        assert!(
+            wrong
+                .dot(&t.vector(&Vec3::new(1., -1., 0.)).normalized())
+                .abs()
+                > 0.1
+        );
+    }
+
+    #[test]
+    fn rotations_keep_lengths_and_turn_counterclockwise() {
+        let rotation = Transform::rotation(&Vec3::new(0., 0., 1.), 90.);
+        assert_close(
+            &rotation.vector(&Vec3::new(1., 0., 0.)),
+            &Vec3::new(0., 1., 0.),
+        );
+        let rotation = Transform::rotation(&Vec3::new(1., 2., 3.), 123.);
+        let v = Vec3::new(-2., 0.5, 4.);
+        assert!((rotation.vector(&v).length() - v.length()).abs() < 1e-12);
+        assert!((rotation.determinant() - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn the_determinant_is_the_volume_scale() {
+        assert!((skewed().determinant() + 3.).abs() < 1e-12);
+        assert!((skewed().inverse().determinant() + 1. / 3.).abs() < 1e-12);
+    }
+}
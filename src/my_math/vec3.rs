@@ -1,6 +1,6 @@
 use core::ops;
 
-use super::prelude::random_double_range;
+use super::prelude::{random_double, random_double_range, PI};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Vec3 {
@@ -13,7 +13,7 @@ impl Vec3 {
     const EPSILON: f64 = 1e-8;
 
     pub fn new(x: f64, y: f64, z: f64) -> Self {
-        Vec3 { x: x, y: y, z: z }
+        Vec3 { x, y, z }
     }
     pub fn length(self) -> f64 {
         self.length_squared().sqrt()
@@ -35,7 +35,7 @@ impl Vec3 {
         self / self.length()
     }
 
-    fn random() -> Self {
+    pub fn random() -> Self {
         Self::random_range(0., 1.)
     }
 
@@ -46,7 +46,7 @@ impl Vec3 {
             z: random_double_range(min, max),
         }
     }
-    fn random_in_unit_sphere() -> Self {
+    pub fn random_in_unit_sphere() -> Self {
         loop {
             let v = Vec3::random_range(-1., 1.);
             if v.length_squared() <= 1. {
@@ -77,10 +77,16 @@ impl Vec3 {
             }
         }
     }
+    // Uniformly distributed unit vector within the cone around +Z whose half-angle has the
+    // cosine `cos_theta_max`.
+    pub fn random_in_cone(cos_theta_max: f64) -> Self {
+        let phi = 2. * PI * random_double();
+        let z = 1. + random_double() * (cos_theta_max - 1.);
+        let sin_theta = (1. - z * z).sqrt();
+        Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z)
+    }
     pub fn near_zero(&self) -> bool {
-        return self.x.abs() < Self::EPSILON
-            && self.y.abs() < Self::EPSILON
-            && self.z.abs() < Self::EPSILON;
+        self.x.abs() < Self::EPSILON && self.y.abs() < Self::EPSILON && self.z.abs() < Self::EPSILON
     }
     pub fn reflect(&self, normal: &Vec3) -> Vec3 {
         *self - *normal * self.dot(normal) * 2.
@@ -142,8 +148,8 @@ where
     type Output = Vec3;
     fn mul(self, rhs: T) -> Vec3 {
         Vec3 {
-            x: self.x * rhs.clone(),
-            y: self.y * rhs.clone(),
+            x: self.x * rhs,
+            y: self.y * rhs,
             z: self.z * rhs,
         }
     }
@@ -166,9 +172,21 @@ where
     type Output = Vec3;
     fn div(self, rhs: T) -> Vec3 {
         Vec3 {
-            x: self.x / rhs.clone(),
-            y: self.y / rhs.clone(),
+            x: self.x / rhs,
+            y: self.y / rhs,
             z: self.z / rhs,
         }
     }
 }
+
+impl ops::Index<usize> for Vec3 {
+    type Output = f64;
+    fn index(&self, axis: usize) -> &f64 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 axis index out of range: {}", axis),
+        }
+    }
+}
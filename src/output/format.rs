@@ -0,0 +1,108 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use super::image::Image;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png8,
+    Png16,
+    // Binary PPM (P6), 8 bits per channel.
+    Ppm,
+    // Portable float map: linear 32-bit floats, no tone mapping or clamping.
+    Pfm,
+}
+
+impl ImageFormat {
+    // Picks the format from a file extension. PNG defaults to 8 bits per channel.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(ImageFormat::Png8),
+            "ppm" => Some(ImageFormat::Ppm),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" | "png8" => Some(ImageFormat::Png8),
+            "png16" => Some(ImageFormat::Png16),
+            "ppm" => Some(ImageFormat::Ppm),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+}
+
+// Saves the image, choosing the format from the extension of `path`.
+pub fn save_image(image: &Image, path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    let format = ImageFormat::from_path(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "cannot guess the image format of {}, use .png, .ppm or .pfm",
+                path.display()
+            ),
+        )
+    })?;
+    save_image_as(image, path, format)
+}
+
+pub fn save_image_as(image: &Image, path: impl AsRef<Path>, format: ImageFormat) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_image(image, format, &mut writer)?;
+    writer.flush()
+}
+
+pub fn write_image(image: &Image, format: ImageFormat, writer: impl Write) -> io::Result<()> {
+    match format {
+        ImageFormat::Png8 => write_png(image, png::BitDepth::Eight, writer),
+        ImageFormat::Png16 => write_png(image, png::BitDepth::Sixteen, writer),
+        ImageFormat::Ppm => write_ppm(image, writer),
+        ImageFormat::Pfm => write_pfm(image, writer),
+    }
+}
+
+fn write_png(image: &Image, bit_depth: png::BitDepth, writer: impl Write) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(writer, image.width() as u32, image.height() as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(bit_depth);
+    let mut writer = encoder.write_header()?;
+
+    let data: Vec<u8> = match bit_depth {
+        png::BitDepth::Sixteen => image
+            .to_rgb16()
+            .into_iter()
+            .flat_map(u16::to_be_bytes)
+            .collect(),
+        _ => image.to_rgb8(),
+    };
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+fn write_ppm(image: &Image, mut writer: impl Write) -> io::Result<()> {
+    write!(writer, "P6\n{} {}\n255\n", image.width(), image.height())?;
+    writer.write_all(&image.to_rgb8())
+}
+
+fn write_pfm(image: &Image, mut writer: impl Write) -> io::Result<()> {
+    // A negative scale marks little-endian data. PFM stores the bottom row first.
+    write!(writer, "PF\n{} {}\n-1.0\n", image.width(), image.height())?;
+    let mut data = Vec::with_capacity(image.width() * image.height() * 12);
+    for y in (0..image.height()).rev() {
+        for c in image.row(y) {
+            for channel in [c.x, c.y, c.z] {
+                data.extend_from_slice(&(channel as f32).to_le_bytes());
+            }
+        }
+    }
+    writer.write_all(&data)
+}
@@ -0,0 +1,134 @@
+use crate::color::Color;
+
+// A rendered frame. Every pixel keeps the sum of the linear (HDR) samples taken for it and
+// how many there were, so more samples can be added later and the average stays exact.
+// Pixels are stored row by row, top row first.
+#[derive(Clone)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    sums: Vec<Color>,
+    sample_counts: Vec<u64>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Image {
+            width,
+            height,
+            sums: vec![Color::new(0., 0., 0.); width * height],
+            sample_counts: vec![0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "pixel ({}, {}) is outside of a {}x{} image",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        y * self.width + x
+    }
+
+    // The average of all samples of the pixel, black if it has none yet.
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        let i = self.index(x, y);
+        Self::average(self.sums[i], self.sample_counts[i])
+    }
+
+    // Overwrites the pixel with a final color, counted as a single sample.
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        let i = self.index(x, y);
+        self.sums[i] = color;
+        self.sample_counts[i] = 1;
+    }
+
+    // Adds `count` samples whose colors add up to `sum`.
+    pub fn add_samples(&mut self, x: usize, y: usize, sum: Color, count: u64) {
+        let i = self.index(x, y);
+        self.sums[i] += sum;
+        self.sample_counts[i] += count;
+    }
+
+    pub fn sample_count(&self, x: usize, y: usize) -> u64 {
+        self.sample_counts[self.index(x, y)]
+    }
+
+    pub fn total_samples(&self) -> u64 {
+        self.sample_counts.iter().sum()
+    }
+
+    // Averaged colors of one row, left to right.
+    pub fn row(&self, y: usize) -> impl DoubleEndedIterator<Item = Color> + '_ {
+        let start = self.index(0, y);
+        self.sums[start..start + self.width]
+            .iter()
+            .zip(&self.sample_counts[start..start + self.width])
+            .map(|(sum, count)| Self::average(*sum, *count))
+    }
+
+    // Averaged colors of all pixels, in row-major order.
+    pub fn pixels(&self) -> impl Iterator<Item = Color> + '_ {
+        self.sums
+            .iter()
+            .zip(&self.sample_counts)
+            .map(|(sum, count)| Self::average(*sum, *count))
+    }
+
+    // Gamma corrected 8-bit RGB triplets, in row-major order.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.pixels().flat_map(Color::to_rgb8).collect()
+    }
+
+    // Gamma corrected 16-bit RGB triplets, in row-major order.
+    pub fn to_rgb16(&self) -> Vec<u16> {
+        self.pixels().flat_map(Color::to_rgb16).collect()
+    }
+
+    // Linear RGB triplets as 32-bit floats, in row-major order.
+    pub fn to_rgb_f32(&self) -> Vec<f32> {
+        self.pixels()
+            .flat_map(|c| [c.x as f32, c.y as f32, c.z as f32])
+            .collect()
+    }
+
+    // False-color image of how many samples each pixel got: blue for the fewest, through
+    // green, to red for the most.
+    pub fn sample_heat_map(&self) -> Image {
+        let min = self.sample_counts.iter().copied().min().unwrap_or(0);
+        let max = self.sample_counts.iter().copied().max().unwrap_or(0);
+        let mut heat_map = Image::new(self.width, self.height);
+        for (i, &count) in self.sample_counts.iter().enumerate() {
+            let t = if max > min {
+                (count - min) as f64 / (max - min) as f64
+            } else {
+                0.
+            };
+            heat_map.sums[i] = if t < 0.5 {
+                Color::new(0., 2. * t, 1. - 2. * t)
+            } else {
+                Color::new(2. * t - 1., 2. - 2. * t, 0.)
+            };
+            heat_map.sample_counts[i] = 1;
+        }
+        heat_map
+    }
+
+    fn average(sum: Color, count: u64) -> Color {
+        if count == 0 {
+            Color::new(0., 0., 0.)
+        } else {
+            sum / count as f64
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::color::Color;
+
+// Running mean and variance of a pixel's sample luminances, updated one sample at a time
+// with Welford's algorithm, which stays accurate over thousands of samples.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PixelStats {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+// Below this luminance the error is measured in absolute rather than relative terms, so
+// that noisy near-black pixels do not take the whole sample budget.
+const MIN_LUMINANCE: f64 = 0.01;
+
+impl PixelStats {
+    pub fn add(&mut self, sample: Color) {
+        let luminance = sample.luminance();
+        self.count += 1;
+        let delta = luminance - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (luminance - self.mean);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    // Unbiased sample variance.
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.
+        } else {
+            self.m2 / (self.count - 1) as f64
+        }
+    }
+
+    // Standard error of the mean, relative to the mean itself.
+    pub fn relative_error(&self) -> f64 {
+        if self.count == 0 {
+            return f64::INFINITY;
+        }
+        (self.variance() / self.count as f64).sqrt() / self.mean.abs().max(MIN_LUMINANCE)
+    }
+}
+
+// When to stop sampling a pixel before `samples_per_pixel` is reached.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveSampling {
+    // Stop once the relative error of the pixel's mean luminance is at most this.
+    pub threshold: f64,
+    // Samples every pixel gets regardless of its error, so that the variance estimate
+    // can be trusted.
+    pub min_samples: u64,
+}
+
+impl AdaptiveSampling {
+    pub fn is_converged(&self, stats: &PixelStats) -> bool {
+        stats.count() >= self.min_samples.max(2) && stats.relative_error() <= self.threshold
+    }
+}
@@ -0,0 +1,205 @@
+use std::{collections::HashMap, sync::Arc};
+
+use super::integrator::RenderContext;
+use crate::{
+    color::Color, hittables::hittable::HitResult, material::material::ScatterResult,
+    my_math::prelude::*, output::image::Image,
+};
+
+// Arbitrary output variables: data about the first surface seen through each pixel,
+// rendered alongside the beauty image for compositing and debugging. Pixels where the
+// camera ray misses everything are zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aov {
+    // World space shading normal, facing the camera.
+    Normal,
+    // World space position.
+    Position,
+    // Distance from the camera, in all three channels.
+    Depth,
+    // Surface color without lighting, as in `AlbedoIntegrator`.
+    Albedo,
+    // Materials numbered from 1 in the order they first appear in the image, scanning row
+    // by row. Not averaged: every pixel takes the ID seen by its first sample.
+    MaterialId,
+    // 1 + the index of the object in the world list. Not averaged either.
+    ObjectId,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 6] = [
+        Aov::Normal,
+        Aov::Position,
+        Aov::Depth,
+        Aov::Albedo,
+        Aov::MaterialId,
+        Aov::ObjectId,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Normal => "normal",
+            Aov::Position => "position",
+            Aov::Depth => "depth",
+            Aov::Albedo => "albedo",
+            Aov::MaterialId => "material_id",
+            Aov::ObjectId => "object_id",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Aov::ALL
+            .into_iter()
+            .find(|aov| aov.name() == name.to_ascii_lowercase().replace('-', "_"))
+    }
+}
+
+// The first hit of one camera ray.
+#[derive(Clone, Copy)]
+pub struct AovSample {
+    normal: Vec3,
+    position: Point3,
+    depth: f64,
+    albedo: Color,
+    // Address of the material, only used to tell materials apart during one render.
+    material: Option<usize>,
+    object: Option<usize>,
+}
+
+impl AovSample {
+    pub fn trace(ray: &Ray, context: &RenderContext) -> Self {
+        match context.hit(ray) {
+            HitResult::Hit(hit_record) => {
+                let albedo = match hit_record.material.scatter(ray, &hit_record) {
+                    ScatterResult::Scatter { attenuation, .. } => attenuation,
+                    ScatterResult::Consume => hit_record.material.emitted(&hit_record),
+                };
+                AovSample {
+                    normal: hit_record.normal,
+                    position: hit_record.intersection_point,
+                    depth: hit_record.t * ray.direction.length(),
+                    albedo,
+                    material: Some(Arc::as_ptr(&hit_record.material) as *const () as usize),
+                    object: Some(hit_record.object_id),
+                }
+            }
+            HitResult::Miss => AovSample {
+                normal: Vec3::new(0., 0., 0.),
+                position: Point3::new(0., 0., 0.),
+                depth: 0.,
+                albedo: Color::new(0., 0., 0.),
+                material: None,
+                object: None,
+            },
+        }
+    }
+}
+
+// The AOV samples of one pixel: sums of the averaged variables and the IDs of the first
+// sample.
+#[derive(Clone, Copy)]
+pub struct AovPixel {
+    normal: Vec3,
+    position: Point3,
+    depth: f64,
+    albedo: Color,
+    ids: Option<(Option<usize>, Option<usize>)>,
+}
+
+impl Default for AovPixel {
+    fn default() -> Self {
+        AovPixel {
+            normal: Vec3::new(0., 0., 0.),
+            position: Point3::new(0., 0., 0.),
+            depth: 0.,
+            albedo: Color::new(0., 0., 0.),
+            ids: None,
+        }
+    }
+}
+
+impl AovPixel {
+    pub fn add(&mut self, sample: &AovSample) {
+        self.normal += sample.normal;
+        self.position += sample.position;
+        self.depth += sample.depth;
+        self.albedo += sample.albedo;
+        self.ids.get_or_insert((sample.material, sample.object));
+    }
+}
+
+// Accumulates the AOV pixels of a render into one image per requested variable.
+pub struct AovImages {
+    width: usize,
+    aovs: Vec<Aov>,
+    images: Vec<Image>,
+    // Material addresses seen by the first sample of every pixel, `Some(None)` for a
+    // miss. They are turned into IDs once the render is done.
+    materials: Vec<Option<Option<usize>>>,
+}
+
+impl AovImages {
+    pub fn new(aovs: &[Aov], width: usize, height: usize) -> Self {
+        AovImages {
+            width,
+            aovs: aovs.to_vec(),
+            images: vec![Image::new(width, height); aovs.len()],
+            materials: vec![None; width * height],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aovs.is_empty()
+    }
+
+    // Adds `count` samples of pixel (x, y) whose variables add up to `pixel`.
+    pub fn add_samples(&mut self, x: usize, y: usize, pixel: &AovPixel, count: u64) {
+        let Some((material, object)) = pixel.ids else {
+            return;
+        };
+        for (aov, image) in self.aovs.iter().zip(&mut self.images) {
+            match aov {
+                Aov::Normal => image.add_samples(x, y, pixel.normal, count),
+                Aov::Position => image.add_samples(x, y, pixel.position, count),
+                Aov::Depth => image.add_samples(
+                    x,
+                    y,
+                    Color::new(pixel.depth, pixel.depth, pixel.depth),
+                    count,
+                ),
+                Aov::Albedo => image.add_samples(x, y, pixel.albedo, count),
+                Aov::MaterialId => (),
+                Aov::ObjectId => {
+                    // The ID of the very first sample wins, later passes leave it alone.
+                    if image.sample_count(x, y) == 0 {
+                        let id = object.map_or(0., |object| (object + 1) as f64);
+                        image.set(x, y, Color::new(id, id, id));
+                    }
+                }
+            }
+        }
+        self.materials[y * self.width + x].get_or_insert(material);
+    }
+
+    // The finished images, in the order the variables were requested.
+    pub fn finish(mut self) -> Vec<(Aov, Image)> {
+        let width = self.width;
+        let mut ids: HashMap<usize, usize> = HashMap::new();
+        for (aov, image) in self.aovs.iter().zip(&mut self.images) {
+            if *aov != Aov::MaterialId {
+                continue;
+            }
+            for (index, material) in self.materials.iter().enumerate() {
+                let id = match material.flatten() {
+                    Some(material) => {
+                        let next_id = ids.len() + 1;
+                        *ids.entry(material).or_insert(next_id) as f64
+                    }
+                    None => 0.,
+                };
+                image.set(index % width, index / width, Color::new(id, id, id));
+            }
+        }
+        self.aovs.into_iter().zip(self.images).collect()
+    }
+}
@@ -0,0 +1,319 @@
+use super::lights::{hits_at, LightList};
+use crate::{
+    camera::Background,
+    color::Color,
+    hittables::hittable::{HitRecord, HitResult, Hittable},
+    material::material::ScatterResult,
+    my_math::prelude::*,
+};
+
+// Everything an integrator needs to know about the scene besides the ray itself.
+pub struct RenderContext<'a> {
+    pub world: &'a dyn Hittable,
+    // The emitters of `world`.
+    pub lights: &'a LightList,
+    pub background: Background,
+    // Paths never have more segments than this.
+    pub max_bounces: u64,
+    // Number of bounces after which paths may be ended early by Russian roulette.
+    pub roulette_depth: u64,
+}
+
+impl RenderContext<'_> {
+    pub fn hit(&self, ray: &Ray) -> HitResult {
+        self.world.hit(ray, &Interval::new(0.01, INFINITY))
+    }
+
+    // Whether nothing blocks the way from `point` along the unit vector `direction` for
+    // `distance` at `time`: a shadow ray.
+    pub fn is_unoccluded(
+        &self,
+        point: &Point3,
+        direction: &Vec3,
+        distance: f64,
+        time: f64,
+    ) -> bool {
+        let shadow_ray = Ray::new(*point, *direction, time);
+        matches!(
+            self.world
+                .hit(&shadow_ray, &Interval::new(0.01, distance - 0.01)),
+            HitResult::Miss
+        )
+    }
+}
+
+// Computes the light arriving at the camera along a camera ray. The camera averages the
+// results of all samples of a pixel, so an integrator may return a noisy estimate.
+pub trait Integrator: Send + Sync {
+    fn radiance(&self, ray: Ray, context: &RenderContext) -> Color;
+}
+
+// Unidirectional path tracing: the path bounces around by sampling the materials until it
+// leaves the scene, is absorbed, or is ended by Russian roulette. Emissive objects are only
+// found by chance, which makes small ones very noisy; see `PathTracer`. Analytic lights
+// cannot be hit at all, so they are sampled with shadow rays.
+pub struct SimplePathTracer;
+
+impl Integrator for SimplePathTracer {
+    // `throughput` is the fraction of light that makes it from the current ray back to
+    // the camera, and everything the path picks up is scaled by it.
+    fn radiance(&self, mut ray: Ray, context: &RenderContext) -> Color {
+        let mut radiance = Color::new(0., 0., 0.);
+        let mut throughput = Color::new(1., 1., 1.);
+
+        for depth in 0..=context.max_bounces {
+            let hit = if depth == context.max_bounces {
+                HitResult::Miss
+            } else {
+                context.hit(&ray)
+            };
+
+            let hit_record = match hit {
+                HitResult::Hit(hit_record) => hit_record,
+                HitResult::Miss => {
+                    radiance += throughput * context.background.color(&ray);
+                    break;
+                }
+            };
+
+            radiance += throughput * hit_record.material.emitted(&hit_record);
+            radiance += throughput * sample_analytic_lights(&ray, &hit_record, context);
+            match hit_record.material.scatter(&ray, &hit_record) {
+                ScatterResult::Scatter {
+                    ray: scattered,
+                    attenuation,
+                } => {
+                    ray = scattered;
+                    throughput = throughput * attenuation;
+                }
+                ScatterResult::Consume => break,
+            }
+
+            // Russian roulette: past `roulette_depth`, dim paths are ended at random and the
+            // survivors are brightened to make up for the ones that were ended, which keeps
+            // the estimate unbiased.
+            if depth + 1 >= context.roulette_depth {
+                let survival = throughput.x.max(throughput.y).max(throughput.z).min(0.95);
+                if survival <= 0. || random_double() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+        radiance
+    }
+}
+
+// Path tracing with next event estimation: at every bounce off a smooth material, a shadow
+// ray is also sent towards a random light. Both ways of finding light are combined with
+// multiple importance sampling, so each one counts most where it has less noise: light
+// sampling for small lights, BSDF sampling for big ones and glossy surfaces.
+pub struct PathTracer;
+
+impl Integrator for PathTracer {
+    fn radiance(&self, mut ray: Ray, context: &RenderContext) -> Color {
+        let mut radiance = Color::new(0., 0., 0.);
+        let mut throughput = Color::new(1., 1., 1.);
+        // The density of the current ray's direction, if it was sampled from a smooth
+        // BSDF. None for camera rays and specular bounces, whose directions light sampling
+        // cannot produce.
+        let mut bsdf_pdf: Option<f64> = None;
+
+        for depth in 0..=context.max_bounces {
+            let hit = if depth == context.max_bounces {
+                HitResult::Miss
+            } else {
+                context.hit(&ray)
+            };
+
+            let hit_record = match hit {
+                HitResult::Hit(hit_record) => hit_record,
+                HitResult::Miss => {
+                    radiance += throughput * context.background.color(&ray);
+                    break;
+                }
+            };
+            let material = &hit_record.material;
+
+            if material.is_emissive() {
+                let weight = match bsdf_pdf {
+                    Some(bsdf_pdf) => {
+                        let light_pdf = context.lights.direction_pdf(&ray, hit_record.t);
+                        power_heuristic(bsdf_pdf, light_pdf)
+                    }
+                    None => 1.,
+                };
+                radiance += throughput * material.emitted(&hit_record) * weight;
+            }
+
+            if context.lights.has_emitters() {
+                radiance += throughput * sample_light(&ray, &hit_record, context);
+            }
+            radiance += throughput * sample_analytic_lights(&ray, &hit_record, context);
+
+            let wo = -ray.direction.normalized();
+            let Some(sample) = material.sample(&hit_record, &wo) else {
+                break;
+            };
+            bsdf_pdf = (!sample.flags.specular).then_some(sample.pdf);
+            throughput = throughput * sample.weight();
+            ray = Ray::new(hit_record.intersection_point, sample.direction, ray.time);
+
+            // Russian roulette, as in `SimplePathTracer`.
+            if depth + 1 >= context.roulette_depth {
+                let survival = throughput.x.max(throughput.y).max(throughput.z).min(0.95);
+                if survival <= 0. || random_double() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+        radiance
+    }
+}
+
+// Light reaching the hit point directly from a randomly chosen emitter and scattered
+// along the incoming ray, weighted for multiple importance sampling with the BSDF.
+fn sample_light(ray: &Ray, hit_record: &HitRecord, context: &RenderContext) -> Color {
+    let black = Color::new(0., 0., 0.);
+    let material = &hit_record.material;
+    let origin = hit_record.intersection_point;
+
+    let wo = -ray.direction.normalized();
+    let (light, direction) = context.lights.sample_direction(&origin, ray.time);
+    let bsdf_pdf = material.pdf(hit_record, &wo, &direction);
+    if bsdf_pdf <= 0. {
+        // A specular surface, or a light behind it.
+        return black;
+    }
+
+    // The shadow ray only counts the sampled light. Anything it hits first is in the way,
+    // emissive or not: BSDF sampling already finds emitters that are not in the light
+    // list.
+    let shadow_ray = Ray::new(origin, direction, ray.time);
+    let HitResult::Hit(light_hit) = context.hit(&shadow_ray) else {
+        return black;
+    };
+    if !light_hit.material.is_emissive() || !hits_at(light, &shadow_ray, light_hit.t) {
+        return black;
+    }
+    let light_pdf = context.lights.direction_pdf(&shadow_ray, light_hit.t);
+    if light_pdf <= 0. {
+        return black;
+    }
+
+    let emitted = light_hit.material.emitted(&light_hit);
+    let scattering = material.eval(hit_record, &wo, &direction);
+    emitted * scattering * (power_heuristic(light_pdf, bsdf_pdf) / light_pdf)
+}
+
+// Light reaching the hit point from every analytic light and scattered along the incoming
+// ray. BSDF sampling can never find these lights, so there is nothing to weight against.
+fn sample_analytic_lights(ray: &Ray, hit_record: &HitRecord, context: &RenderContext) -> Color {
+    let mut radiance = Color::new(0., 0., 0.);
+    let wo = -ray.direction.normalized();
+    let origin = hit_record.intersection_point;
+    for light in context.lights.analytic() {
+        let Some(sample) = light.sample(&origin) else {
+            continue;
+        };
+        let scattering = hit_record.material.eval(hit_record, &wo, &sample.direction);
+        if scattering.x <= 0. && scattering.y <= 0. && scattering.z <= 0. {
+            // No need for a shadow ray.
+            continue;
+        }
+        if context.is_unoccluded(&origin, &sample.direction, sample.distance, ray.time) {
+            radiance += scattering * sample.irradiance;
+        }
+    }
+    radiance
+}
+
+// Weight of a sample taken with density `pdf` against another strategy with density
+// `other_pdf`. The weights of both strategies for the same direction add up to one.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0. {
+        0.
+    } else {
+        a / (a + b)
+    }
+}
+
+// Shading normal of the first hit, mapped from [-1, 1] to [0, 1] per axis.
+pub struct NormalsIntegrator;
+
+impl Integrator for NormalsIntegrator {
+    fn radiance(&self, ray: Ray, context: &RenderContext) -> Color {
+        match context.hit(&ray) {
+            HitResult::Hit(hit_record) => (hit_record.normal + Color::new(1., 1., 1.)) * 0.5,
+            HitResult::Miss => Color::new(0., 0., 0.),
+        }
+    }
+}
+
+// Distance to the first hit as a shade of grey: white right at the camera, fading to black
+// at `max_distance` and beyond.
+pub struct DepthIntegrator {
+    pub max_distance: f64,
+}
+
+impl Integrator for DepthIntegrator {
+    fn radiance(&self, ray: Ray, context: &RenderContext) -> Color {
+        match context.hit(&ray) {
+            HitResult::Hit(hit_record) => {
+                let distance = hit_record.t * ray.direction.length();
+                let shade = 1. - (distance / self.max_distance).clamp(0., 1.);
+                Color::new(shade, shade, shade)
+            }
+            HitResult::Miss => Color::new(0., 0., 0.),
+        }
+    }
+}
+
+// Surface color of the first hit without any lighting: the attenuation of a scattered ray,
+// or the emitted light for lights. Misses show the background.
+pub struct AlbedoIntegrator;
+
+impl Integrator for AlbedoIntegrator {
+    fn radiance(&self, ray: Ray, context: &RenderContext) -> Color {
+        match context.hit(&ray) {
+            HitResult::Hit(hit_record) => match hit_record.material.scatter(&ray, &hit_record) {
+                ScatterResult::Scatter { attenuation, .. } => attenuation,
+                ScatterResult::Consume => hit_record.material.emitted(&hit_record),
+            },
+            HitResult::Miss => context.background.color(&ray),
+        }
+    }
+}
+
+// Ambient occlusion: white where a cosine-distributed ray from the first hit escapes
+// further than `distance`, black where it is blocked. Averaged over the samples of a pixel
+// this shows how enclosed every point is.
+pub struct AmbientOcclusionIntegrator {
+    pub distance: f64,
+}
+
+impl Integrator for AmbientOcclusionIntegrator {
+    fn radiance(&self, ray: Ray, context: &RenderContext) -> Color {
+        let HitResult::Hit(hit_record) = context.hit(&ray) else {
+            return Color::new(1., 1., 1.);
+        };
+        let mut direction = hit_record.normal + Vec3::random_unit_vector();
+        if direction.length() < 1e-8 {
+            direction = hit_record.normal;
+        }
+        let occlusion_ray = Ray::new(
+            hit_record.intersection_point,
+            direction.normalized(),
+            ray.time,
+        );
+        match context
+            .world
+            .hit(&occlusion_ray, &Interval::new(0.01, self.distance))
+        {
+            HitResult::Hit(_) => Color::new(0., 0., 0.),
+            HitResult::Miss => Color::new(1., 1., 1.),
+        }
+    }
+}
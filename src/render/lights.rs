@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use crate::{
+    hittables::hittable::{HitResult, Hittable},
+    light::light::Light,
+    my_math::prelude::*,
+};
+
+// The lights of a scene, which integrators sample directly: the emissive objects of the
+// world and the analytic lights.
+pub struct LightList {
+    emitters: Vec<Arc<dyn Hittable>>,
+    analytic: Vec<Arc<dyn Light>>,
+}
+
+impl LightList {
+    pub fn new(world: &dyn Hittable, analytic: &[Arc<dyn Light>]) -> Self {
+        let mut emitters = Vec::new();
+        world.collect_emitters(&mut emitters);
+        LightList {
+            emitters,
+            analytic: analytic.to_vec(),
+        }
+    }
+
+    pub fn has_emitters(&self) -> bool {
+        !self.emitters.is_empty()
+    }
+
+    pub fn analytic(&self) -> &[Arc<dyn Light>] {
+        &self.analytic
+    }
+
+    // One of the emitters, chosen uniformly, and a unit vector from `origin` towards it.
+    // There must be at least one.
+    pub fn sample_direction(&self, origin: &Point3, time: f64) -> (&dyn Hittable, Vec3) {
+        let index =
+            ((random_double() * self.emitters.len() as f64) as usize).min(self.emitters.len() - 1);
+        let emitter = self.emitters[index].as_ref();
+        (emitter, emitter.sample_direction(origin, time))
+    }
+
+    // Density over solid angle with which `sample_direction` picks the direction of `ray`
+    // and an emitter that the ray reaches at `t`, the first hit along it. Shadow rays only
+    // count the sampled emitter, so emitters hidden further along do not add to this.
+    pub fn direction_pdf(&self, ray: &Ray, t: f64) -> f64 {
+        if self.emitters.is_empty() {
+            return 0.;
+        }
+        let sum: f64 = self
+            .emitters
+            .iter()
+            .filter(|emitter| hits_at(emitter.as_ref(), ray, t))
+            .map(|emitter| emitter.direction_pdf(&ray.origin, &ray.direction, ray.time))
+            .sum();
+        sum / self.emitters.len() as f64
+    }
+}
+
+// Whether `ray` hits `object` at `t`, give or take rounding.
+pub fn hits_at(object: &dyn Hittable, ray: &Ray, t: f64) -> bool {
+    let tolerance = 1e-6 * t.abs().max(1.);
+    matches!(
+        object.hit(ray, &Interval::new(t - tolerance, t + tolerance)),
+        HitResult::Hit(_)
+    )
+}
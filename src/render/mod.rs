@@ -0,0 +1,5 @@
+pub mod adaptive;
+pub mod aov;
+pub mod integrator;
+pub mod lights;
+pub mod tile;
@@ -0,0 +1,122 @@
+use std::cmp::Ordering;
+
+// A rectangle of pixels rendered as one unit of work. Tiles on the right and bottom edges
+// are smaller when the image size is not a multiple of the tile size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    pub fn pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    // Image coordinates of the tile's pixels, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+// The order in which tiles are handed out to the render threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileOrder {
+    // Left to right, top to bottom.
+    #[default]
+    Scanline,
+    // Outwards from the center of the image, where the subject usually is.
+    Spiral,
+    // Along a Hilbert curve, so consecutive tiles are neighbours and share cached geometry.
+    Hilbert,
+}
+
+impl TileOrder {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "scanline" => Some(TileOrder::Scanline),
+            "spiral" => Some(TileOrder::Spiral),
+            "hilbert" => Some(TileOrder::Hilbert),
+            _ => None,
+        }
+    }
+}
+
+// Splits a `width` x `height` image into square tiles of side `tile_size`, listed in `order`.
+pub fn make_tiles(width: usize, height: usize, tile_size: usize, order: TileOrder) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let columns = width.div_ceil(tile_size);
+    let rows = height.div_ceil(tile_size);
+    let tile_at = |column: usize, row: usize| {
+        let (x, y) = (column * tile_size, row * tile_size);
+        Tile {
+            x,
+            y,
+            width: tile_size.min(width - x),
+            height: tile_size.min(height - y),
+        }
+    };
+
+    match order {
+        TileOrder::Scanline => (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| tile_at(column, row))
+            .collect(),
+        TileOrder::Spiral => {
+            let mut tiles: Vec<Tile> = (0..rows)
+                .flat_map(|row| (0..columns).map(move |column| (column, row)))
+                .map(|(column, row)| tile_at(column, row))
+                .collect();
+            // Ring by ring around the center, each ring swept by angle.
+            let key = |tile: &Tile| {
+                let dx =
+                    (tile.x as f64 + tile.width as f64 / 2. - width as f64 / 2.) / tile_size as f64;
+                let dy = (tile.y as f64 + tile.height as f64 / 2. - height as f64 / 2.)
+                    / tile_size as f64;
+                (dx.abs().max(dy.abs()).round(), dy.atan2(dx))
+            };
+            tiles.sort_by(|a, b| {
+                let (a, b) = (key(a), key(b));
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            });
+            tiles
+        }
+        TileOrder::Hilbert => {
+            // Walk the curve over the smallest power-of-two square covering the tile grid and
+            // skip the points outside of it.
+            let side = columns.max(rows).next_power_of_two();
+            (0..side * side)
+                .map(|d| hilbert_point(side, d))
+                .filter(|&(column, row)| column < columns && row < rows)
+                .map(|(column, row)| tile_at(column, row))
+                .collect()
+        }
+    }
+}
+
+// The `d`-th point of the Hilbert curve filling a `side` x `side` grid, `side` being a power
+// of two.
+fn hilbert_point(side: usize, d: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < side {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
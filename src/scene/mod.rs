@@ -0,0 +1,3 @@
+pub mod obj;
+pub mod mtl;
+pub mod scene_file;
@@ -0,0 +1,258 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::obj::ObjError;
+use crate::{
+    color::Color,
+    material::material::{Dielectric, Lambertian, Material, Metal},
+    texture::texture::ImageTexture,
+};
+
+// The subset of an MTL material statement that maps onto our materials.
+#[derive(Debug, Clone)]
+pub struct MtlMaterial {
+    pub name: String,
+    pub diffuse: Color,               // Kd
+    pub specular: Color,              // Ks
+    pub specular_exponent: f64,       // Ns
+    pub optical_density: f64,         // Ni
+    pub dissolve: f64,                // d (or 1 - Tr)
+    pub transmission_filter: Color,   // Tf
+    pub illumination_model: u32,      // illum
+    pub diffuse_map: Option<PathBuf>, // map_Kd, resolved relative to the MTL file
+}
+
+impl MtlMaterial {
+    pub fn new(name: &str) -> Self {
+        // Defaults follow the MTL specification where it has one.
+        MtlMaterial {
+            name: name.to_string(),
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::new(0., 0., 0.),
+            specular_exponent: 0.,
+            optical_density: 1.5,
+            dissolve: 1.,
+            transmission_filter: Color::new(1., 1., 1.),
+            illumination_model: 2,
+            diffuse_map: None,
+        }
+    }
+
+    // Fails only if the diffuse texture map cannot be loaded.
+    pub fn to_material(&self) -> io::Result<Arc<dyn Material>> {
+        // illum 4, 6, 7 and 9 are the transparent models, 3, 5 and 8 the reflective ones.
+        // Anything partially dissolved is treated as glass as well.
+        let transparent = matches!(self.illumination_model, 4 | 6 | 7 | 9) || self.dissolve < 1.;
+        let reflective = matches!(self.illumination_model, 3 | 5 | 8)
+            || (self.illumination_model == 2
+                && max_component(&self.diffuse) <= 0.
+                && max_component(&self.specular) > 0.);
+
+        Ok(if transparent {
+            Arc::new(Dielectric::new(
+                self.transmission_filter,
+                self.optical_density,
+            ))
+        } else if reflective {
+            // Map the Phong exponent onto a fuzz factor: a sharp highlight (big Ns) means
+            // an almost perfect mirror.
+            let fuzz = (2. / (self.specular_exponent + 2.)).sqrt();
+            Arc::new(Metal::new(self.specular, fuzz))
+        } else if let Some(diffuse_map) = &self.diffuse_map {
+            Arc::new(Lambertian::from_texture(Arc::new(ImageTexture::load(
+                diffuse_map,
+            )?)))
+        } else {
+            Arc::new(Lambertian::new(self.diffuse))
+        })
+    }
+}
+
+fn max_component(color: &Color) -> f64 {
+    color.x.max(color.y).max(color.z)
+}
+
+pub fn load_mtl(path: &Path) -> Result<HashMap<String, MtlMaterial>, ObjError> {
+    let source = std::fs::read_to_string(path).map_err(|error| ObjError::io(path, error))?;
+    parse_mtl(&source, path)
+}
+
+pub fn parse_mtl(source: &str, path: &Path) -> Result<HashMap<String, MtlMaterial>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<MtlMaterial> = None;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let error = |message: String| ObjError::parse(path, line_number, message);
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            let name = arguments.join(" ");
+            if name.is_empty() {
+                return Err(error("`newmtl` needs a material name".to_string()));
+            }
+            if let Some(done) = current.replace(MtlMaterial::new(&name)) {
+                materials.insert(done.name.clone(), done);
+            }
+            continue;
+        }
+
+        let Some(material) = current.as_mut() else {
+            // Statements before the first `newmtl` have nothing to apply to.
+            if matches!(
+                keyword,
+                "Kd" | "Ks" | "Ns" | "Ni" | "d" | "Tr" | "Tf" | "illum"
+            ) {
+                return Err(error(format!("`{}` appears before any `newmtl`", keyword)));
+            }
+            continue;
+        };
+
+        match keyword {
+            "Kd" => material.diffuse = parse_color(keyword, &arguments).map_err(error)?,
+            "Ks" => material.specular = parse_color(keyword, &arguments).map_err(error)?,
+            "Tf" => {
+                material.transmission_filter = parse_color(keyword, &arguments).map_err(error)?
+            }
+            "Ns" => {
+                material.specular_exponent = parse_scalar(keyword, &arguments).map_err(error)?
+            }
+            "Ni" => material.optical_density = parse_scalar(keyword, &arguments).map_err(error)?,
+            "d" => material.dissolve = parse_scalar(keyword, &arguments).map_err(error)?,
+            "Tr" => material.dissolve = 1. - parse_scalar(keyword, &arguments).map_err(error)?,
+            "illum" => {
+                material.illumination_model = arguments
+                    .first()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| error("`illum` needs an integer model number".to_string()))?
+            }
+            "map_Kd" => {
+                // Map options such as `-s 1 1 1` come before the file name.
+                let file = arguments
+                    .last()
+                    .ok_or_else(|| error("`map_Kd` needs a file name".to_string()))?;
+                material.diffuse_map = Some(path.parent().unwrap_or(Path::new("")).join(file));
+            }
+            // Other texture maps, emission etc. are not supported yet and are skipped.
+            _ => (),
+        }
+    }
+
+    if let Some(done) = current {
+        materials.insert(done.name.clone(), done);
+    }
+    Ok(materials)
+}
+
+fn parse_scalar(keyword: &str, arguments: &[&str]) -> Result<f64, String> {
+    arguments
+        .first()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("`{}` needs a number", keyword))
+}
+
+fn parse_color(keyword: &str, arguments: &[&str]) -> Result<Color, String> {
+    let values: Vec<f64> = arguments
+        .iter()
+        .map(|value| value.parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("`{}` needs numeric color components", keyword))?;
+    match values[..] {
+        // A single value is a grey level.
+        [v] => Ok(Color::new(v, v, v)),
+        [r, g, b] => Ok(Color::new(r, g, b)),
+        _ => Err(format!(
+            "`{}` needs one or three color components, got {}",
+            keyword,
+            values.len()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> HashMap<String, MtlMaterial> {
+        parse_mtl(source, Path::new("models/test.mtl")).unwrap()
+    }
+
+    fn parse_error_line(source: &str) -> usize {
+        match parse_mtl(source, Path::new("models/test.mtl")) {
+            Err(ObjError::Parse { line, .. }) => line,
+            Err(error) => panic!("expected a parse error, got {}", error),
+            Ok(_) => panic!("expected a parse error for {:?}", source),
+        }
+    }
+
+    #[test]
+    fn statements_apply_to_the_last_material() {
+        let materials = parse(
+            "# two materials\n\
+             newmtl red paint\n\
+             Kd 0.8 0.1 0.1\n\
+             Ks 0.5\n\
+             Ns 100 # shiny\n\
+             illum 3\n\
+             newmtl glass\n\
+             Ni 1.33\n\
+             Tr 0.25\n\
+             Tf 0.9 1 0.9\n\
+             map_Kd -s 2 2 1 textures/glass.png\n",
+        );
+        assert_eq!(materials.len(), 2);
+
+        let red = &materials["red paint"];
+        assert_eq!(red.name, "red paint");
+        assert!((red.diffuse - Color::new(0.8, 0.1, 0.1)).near_zero());
+        assert!((red.specular - Color::new(0.5, 0.5, 0.5)).near_zero());
+        assert_eq!(red.specular_exponent, 100.);
+        assert_eq!(red.illumination_model, 3);
+        assert_eq!(red.dissolve, 1.);
+        assert_eq!(red.diffuse_map, None);
+
+        let glass = &materials["glass"];
+        assert_eq!(glass.optical_density, 1.33);
+        assert_eq!(glass.dissolve, 0.75);
+        assert!((glass.transmission_filter - Color::new(0.9, 1., 0.9)).near_zero());
+        assert_eq!(
+            glass.diffuse_map.as_deref(),
+            Some(Path::new("models/textures/glass.png"))
+        );
+        // Unset properties keep their defaults.
+        assert!((glass.diffuse - Color::new(0.8, 0.8, 0.8)).near_zero());
+        assert_eq!(glass.illumination_model, 2);
+    }
+
+    #[test]
+    fn unsupported_statements_are_skipped() {
+        let materials = parse("newmtl lamp\nKe 1 1 1\nmap_Bump bump.png\nKd 0.5\n");
+        assert!((materials["lamp"].diffuse - Color::new(0.5, 0.5, 0.5)).near_zero());
+    }
+
+    #[test]
+    fn malformed_lines_are_errors() {
+        for (source, line) in [
+            ("Kd 1 1 1\n", 1),
+            ("newmtl\n", 1),
+            ("newmtl a\nKd 1 1\n", 2),
+            ("newmtl a\nKd red\n", 2),
+            ("newmtl a\nNs\n", 2),
+            ("newmtl a\n\nd half\n", 3),
+            ("newmtl a\nillum 2.5\n", 2),
+            ("newmtl a\nmap_Kd\n", 2),
+        ] {
+            assert_eq!(parse_error_line(source), line, "{:?}", source);
+        }
+    }
+}
@@ -0,0 +1,490 @@
+use std::{
+    collections::HashMap,
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::mtl::{load_mtl, MtlMaterial};
+use crate::{
+    color::Color,
+    hittables::{hittable_list::HittableList, triangle_mesh::TriangleMesh},
+    material::material::{Lambertian, Material},
+    my_math::prelude::*,
+};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl ObjError {
+    pub fn io(path: &Path, error: io::Error) -> Self {
+        ObjError::Io {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+    pub fn parse(path: &Path, line: usize, message: String) -> Self {
+        ObjError::Parse {
+            path: path.to_path_buf(),
+            line,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ObjError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io { error, .. } => Some(error),
+            ObjError::Parse { .. } => None,
+        }
+    }
+}
+
+// One corner of a face: indices into the position, texcoord and normal arrays.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct FaceVertex {
+    position: usize,
+    texcoord: Option<usize>,
+    normal: Option<usize>,
+}
+
+// Triangles sharing a group and a material, which become one `TriangleMesh`.
+struct MeshBuilder {
+    material: Option<String>,
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    has_all_normals: bool,
+    texcoords: Vec<(f64, f64)>,
+    has_all_texcoords: bool,
+    indices: Vec<[usize; 3]>,
+    // OBJ indexes positions, texcoords and normals separately, while a mesh has a single
+    // index per vertex, so every distinct combination becomes its own mesh vertex.
+    vertex_lookup: HashMap<FaceVertex, usize>,
+}
+
+impl MeshBuilder {
+    fn new(material: Option<String>) -> Self {
+        MeshBuilder {
+            material,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            has_all_normals: true,
+            texcoords: Vec::new(),
+            has_all_texcoords: true,
+            indices: Vec::new(),
+            vertex_lookup: HashMap::new(),
+        }
+    }
+
+    fn vertex(
+        &mut self,
+        vertex: FaceVertex,
+        positions: &[Point3],
+        normals: &[Vec3],
+        texcoords: &[(f64, f64)],
+    ) -> usize {
+        if let Some(&index) = self.vertex_lookup.get(&vertex) {
+            return index;
+        }
+        let index = self.positions.len();
+        self.positions.push(positions[vertex.position]);
+        match vertex.normal {
+            Some(normal) => self.normals.push(normals[normal]),
+            None => {
+                self.has_all_normals = false;
+                self.normals.push(Vec3::new(0., 0., 0.));
+            }
+        }
+        match vertex.texcoord {
+            Some(texcoord) => self.texcoords.push(texcoords[texcoord]),
+            None => {
+                self.has_all_texcoords = false;
+                self.texcoords.push((0., 0.));
+            }
+        }
+        self.vertex_lookup.insert(vertex, index);
+        index
+    }
+
+    fn build(self, material: Arc<dyn Material>) -> TriangleMesh {
+        TriangleMesh::with_attributes(
+            self.positions,
+            self.has_all_normals.then_some(self.normals),
+            self.has_all_texcoords.then_some(self.texcoords),
+            self.indices,
+            material,
+        )
+    }
+}
+
+// Loads a Wavefront OBJ file, and the MTL libraries it references, into a list with one
+// `TriangleMesh` per group and material. Faces with more than three vertices are
+// triangulated as fans, so they are expected to be convex. Like most readers, faces using
+// a material that no library defines get a default grey instead of failing the file,
+// since OBJ files often travel without their MTL. Libraries that cannot be read are
+// skipped for the same reason.
+pub fn load_obj(path: impl AsRef<Path>) -> Result<HittableList, ObjError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|error| ObjError::io(path, error))?;
+    parse_obj(&source, path)
+}
+
+// Parses OBJ source text. `path` is used for error messages and to resolve `mtllib`
+// statements relative to the OBJ file.
+pub fn parse_obj(source: &str, path: &Path) -> Result<HittableList, ObjError> {
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut positions: Vec<Point3> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut texcoords: Vec<(f64, f64)> = Vec::new();
+
+    let mut mtl_materials: HashMap<String, MtlMaterial> = HashMap::new();
+    let mut finished_meshes: Vec<MeshBuilder> = Vec::new();
+    let mut current = MeshBuilder::new(None);
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let error = |message: String| ObjError::parse(path, line_number, message);
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => positions.push(parse_vec3(keyword, &arguments).map_err(error)?),
+            "vn" => normals.push(parse_vec3(keyword, &arguments).map_err(error)?.normalized()),
+            "vt" => {
+                // A third (w) coordinate is allowed but not used.
+                let values: Vec<f64> = arguments
+                    .iter()
+                    .map(|value| value.parse::<f64>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| error("`vt` needs numeric texture coordinates".to_string()))?;
+                match values[..] {
+                    [u] => texcoords.push((u, 0.)),
+                    [u, v] | [u, v, _] => texcoords.push((u, v)),
+                    _ => return Err(error("`vt` needs 1 to 3 coordinates".to_string())),
+                }
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(error(format!(
+                        "a face needs at least 3 vertices, got {}",
+                        arguments.len()
+                    )));
+                }
+                let corners: Vec<FaceVertex> = arguments
+                    .iter()
+                    .map(|corner| {
+                        parse_face_vertex(corner, positions.len(), texcoords.len(), normals.len())
+                    })
+                    .collect::<Result<_, _>>()
+                    .map_err(error)?;
+
+                let corners: Vec<usize> = corners
+                    .into_iter()
+                    .map(|corner| current.vertex(corner, &positions, &normals, &texcoords))
+                    .collect();
+                for i in 1..corners.len() - 1 {
+                    current
+                        .indices
+                        .push([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            "g" | "o" | "usemtl" => {
+                let material = if keyword == "usemtl" {
+                    Some(arguments.join(" "))
+                } else {
+                    current.material.clone()
+                };
+                let next = MeshBuilder::new(material);
+                finished_meshes.push(std::mem::replace(&mut current, next));
+            }
+            "mtllib" => {
+                if arguments.is_empty() {
+                    return Err(error("`mtllib` needs a file name".to_string()));
+                }
+                for library in arguments {
+                    match load_mtl(&base_dir.join(library)) {
+                        Ok(materials) => mtl_materials.extend(materials),
+                        // Its materials are then unknown, and get the default like any other.
+                        Err(ObjError::Io { .. }) => (),
+                        Err(error) => return Err(error),
+                    }
+                }
+            }
+            // Smoothing groups, lines, points and free-form geometry are ignored.
+            _ => (),
+        }
+    }
+    finished_meshes.push(current);
+
+    let default_material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+
+    let mut world = HittableList::new_empty();
+    for mesh in finished_meshes {
+        if mesh.indices.is_empty() {
+            continue;
+        }
+        let mtl = mesh
+            .material
+            .as_ref()
+            .and_then(|name| mtl_materials.get(name));
+        let material = match mtl {
+            Some(mtl) => match materials.get(&mtl.name) {
+                Some(material) => Arc::clone(material),
+                None => {
+                    let material = mtl.to_material().map_err(|error| {
+                        ObjError::io(mtl.diffuse_map.as_deref().unwrap_or(path), error)
+                    })?;
+                    materials.insert(mtl.name.clone(), Arc::clone(&material));
+                    material
+                }
+            },
+            None => Arc::clone(&default_material),
+        };
+        world.add(Arc::new(mesh.build(material)));
+    }
+    Ok(world)
+}
+
+fn parse_vec3(keyword: &str, arguments: &[&str]) -> Result<Vec3, String> {
+    let values: Vec<f64> = arguments
+        .iter()
+        .take(3)
+        .map(|value| value.parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("`{}` needs numeric coordinates", keyword))?;
+    match values[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => Err(format!("`{}` needs 3 coordinates", keyword)),
+    }
+}
+
+fn parse_face_vertex(
+    corner: &str,
+    position_count: usize,
+    texcoord_count: usize,
+    normal_count: usize,
+) -> Result<FaceVertex, String> {
+    // Accepted forms: `v`, `v/vt`, `v//vn` and `v/vt/vn`.
+    let mut parts = corner.split('/');
+    let position = parts
+        .next()
+        .ok_or_else(|| format!("empty face vertex `{}`", corner))?;
+    let texcoord = parts.next().filter(|part| !part.is_empty());
+    let normal = parts.next().filter(|part| !part.is_empty());
+    if parts.next().is_some() {
+        return Err(format!("malformed face vertex `{}`", corner));
+    }
+
+    Ok(FaceVertex {
+        position: resolve_index(position, position_count, "position")?,
+        texcoord: texcoord
+            .map(|index| resolve_index(index, texcoord_count, "texture coordinate"))
+            .transpose()?,
+        normal: normal
+            .map(|index| resolve_index(index, normal_count, "normal"))
+            .transpose()?,
+    })
+}
+
+fn resolve_index(index: &str, count: usize, what: &str) -> Result<usize, String> {
+    // OBJ indices start at 1, negative ones count back from the last element read so far.
+    let index: i64 = index
+        .parse()
+        .map_err(|_| format!("invalid {} index `{}`", what, index))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!(
+            "{} index {} is out of range, {} defined so far",
+            what, index, count
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittables::hittable::{HitRecord, HitResult, Hittable};
+
+    // The unit square in the z = 0 plane, counterclockwise seen from +z.
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    fn parse(source: &str) -> HittableList {
+        parse_obj(source, Path::new("test.obj")).unwrap()
+    }
+
+    // The hit of a ray coming straight down onto the point (x, y) of the z = 0 plane.
+    fn hit_at(world: &HittableList, x: f64, y: f64) -> Option<HitRecord> {
+        let ray = Ray::new(Point3::new(x, y, 1.), Vec3::new(0., 0., -1.), 0.);
+        match world.hit(&ray, &Interval::new(0.001, INFINITY)) {
+            HitResult::Hit(rec) => Some(rec),
+            HitResult::Miss => None,
+        }
+    }
+
+    fn parse_error_line(source: &str) -> usize {
+        match parse_obj(source, Path::new("test.obj")) {
+            Err(ObjError::Parse { line, .. }) => line,
+            Err(error) => panic!("expected a parse error, got {}", error),
+            Ok(_) => panic!("expected a parse error for {:?}", source),
+        }
+    }
+
+    #[test]
+    fn polygons_are_triangulated_as_fans() {
+        let world = parse(&format!("{}f 1 2 3 4\n", SQUARE));
+        assert_eq!(world.len(), 1);
+        // One point in each of the triangles 1 2 3 and 1 3 4, and one outside.
+        assert!(hit_at(&world, 0.75, 0.25).is_some());
+        assert!(hit_at(&world, 0.25, 0.75).is_some());
+        assert!(hit_at(&world, 1.25, 0.5).is_none());
+
+        // A convex pentagon, the unit square with a point on top.
+        let world = parse(&format!("{}v 0.5 1.5 0\nf 1 2 3 5 4\n", SQUARE));
+        for (x, y) in [(0.9, 0.1), (0.1, 0.9), (0.5, 1.3), (0.2, 0.2)] {
+            assert!(hit_at(&world, x, y).is_some(), "missed ({}, {})", x, y);
+        }
+        assert!(hit_at(&world, 0.1, 1.3).is_none());
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_last_element() {
+        let world = parse(&format!("{}f -4 -3 -2 -1\n", SQUARE));
+        assert!(hit_at(&world, 0.25, 0.75).is_some());
+
+        // Relative to what has been read so far, not to the whole file.
+        let world = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 5 5 5\n");
+        assert!(hit_at(&world, 0.25, 0.25).is_some());
+
+        let texcoords = "vt 0 0\nvt 1 0\nvt 0 1\n";
+        let world = parse(&format!("{}{}f 1/-3 2/-2 4/-1\n", SQUARE, texcoords));
+        let rec = hit_at(&world, 0.25, 0.5).unwrap();
+        assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+
+        assert_eq!(parse_error_line(&format!("{}f -5 -3 -2\n", SQUARE)), 5);
+    }
+
+    #[test]
+    fn face_vertices_with_texture_coordinates_and_normals() {
+        // Texture coordinates twice the position, and normals tilted towards +x.
+        let attributes = "vt 0 0\nvt 2 0\nvt 2 2\nvt 0 2\nvn 1 0 1\n";
+        let source = format!("{}{}", SQUARE, attributes);
+        let tilted = Vec3::new(1., 0., 1.).normalized();
+
+        let world = parse(&format!("{}f 1/1 2/2 3/3 4/4\n", source));
+        let rec = hit_at(&world, 0.25, 0.5).unwrap();
+        assert!((rec.u - 0.5).abs() < 1e-9 && (rec.v - 1.).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(0., 0., 1.)).near_zero());
+
+        let world = parse(&format!("{}f 1//1 2//1 3//1 4//1\n", source));
+        let rec = hit_at(&world, 0.25, 0.5).unwrap();
+        assert!((rec.normal - tilted).near_zero());
+
+        let world = parse(&format!("{}f 1/1/1 2/2/1 3/3/1 4/4/1\n", source));
+        let rec = hit_at(&world, 0.25, 0.5).unwrap();
+        assert!((rec.u - 0.5).abs() < 1e-9 && (rec.v - 1.).abs() < 1e-9);
+        assert!((rec.normal - tilted).near_zero());
+
+        // A mesh only gets normals if every vertex has one.
+        let world = parse(&format!("{}f 1//1 2 3//1 4//1\n", source));
+        let rec = hit_at(&world, 0.25, 0.5).unwrap();
+        assert!((rec.normal - Vec3::new(0., 0., 1.)).near_zero());
+    }
+
+    #[test]
+    fn groups_and_materials_split_meshes() {
+        let world = parse(&format!("{}f 1 2 3\ng other\nf 1 3 4\n", SQUARE));
+        assert_eq!(world.len(), 2);
+        // Empty groups are dropped.
+        let world = parse(&format!("g a\ng b\n{}f 1 2 3\ng c\n", SQUARE));
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn unknown_materials_fall_back_to_the_default() {
+        let world = parse(&format!("{}usemtl missing\nf 1 2 3 4\n", SQUARE));
+        assert_eq!(world.len(), 1);
+        assert!(hit_at(&world, 0.5, 0.5).is_some());
+    }
+
+    #[test]
+    fn missing_libraries_are_skipped() {
+        let source = format!(
+            "mtllib does-not-exist.mtl\n{}usemtl paint\nf 1 2 3 4\n",
+            SQUARE
+        );
+        let world = parse(&source);
+        assert_eq!(world.len(), 1);
+        assert!(hit_at(&world, 0.5, 0.5).is_some());
+    }
+
+    #[test]
+    fn malformed_lines_are_errors() {
+        for (source, line) in [
+            ("v 1 2\n", 1),
+            ("v 1 two 3\n", 1),
+            ("vn 0 0\n", 1),
+            ("vt\n", 1),
+            ("vt 1 2 3 4\n", 1),
+            ("vt u v\n", 1),
+            ("mtllib\n", 1),
+        ] {
+            assert_eq!(parse_error_line(source), line, "{:?}", source);
+        }
+        for (face, line) in [
+            ("f 1 2\n", 5),
+            ("f 0 1 2\n", 5),
+            ("f 1 2 5\n", 5),
+            ("f 1 2 x\n", 5),
+            ("f 1/1 2 3\n", 5),
+            ("f 1//1 2 3\n", 5),
+            ("f 1/1/1/1 2 3\n", 5),
+            ("# comment\n\nf 1 2\n", 7),
+        ] {
+            let source = format!("{}{}", SQUARE, face);
+            assert_eq!(parse_error_line(&source), line, "{:?}", face);
+        }
+    }
+
+    #[test]
+    fn comments_and_unsupported_statements_are_skipped() {
+        let source = format!("# a square\n{}s 1\nl 1 2\nf 1 2 3 4 # the face\n", SQUARE);
+        let world = parse(&source);
+        assert!(hit_at(&world, 0.5, 0.5).is_some());
+    }
+}
@@ -0,0 +1,1063 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use super::obj::load_obj;
+use crate::{
+    camera::{Background, Camera},
+    color::Color,
+    hittables::{
+        bvh::BvhNode,
+        capsule::Capsule,
+        constant_medium::ConstantMedium,
+        cylinder::{Cone, Cylinder},
+        disk::Disk,
+        hittable::Hittable,
+        hittable_list::HittableList,
+        instance::Instance,
+        plane::Plane,
+        quad::{make_box, Quad},
+        sphere::Sphere,
+        torus::Torus,
+        triangle::Triangle,
+        triangle_mesh::TriangleMesh,
+    },
+    light::light::{DirectionalLight, Light, PointLight, SpotLight},
+    material::material::{
+        Dielectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, Material, Metal,
+    },
+    my_math::prelude::*,
+    texture::texture::{CheckerTexture, ImageTexture, NoiseTexture, SolidColor, Texture},
+};
+
+// A scene file is a TOML document with a `[camera]` table, named `[textures.<name>]` and
+// `[materials.<name>]` tables, named `[[prototypes.<name>]]` arrays of objects,
+// `[[objects]]` and `[[lights]]` arrays, and an optional `[fog]` table. For example:
+//
+//     [camera]
+//     image_width = 400
+//     look_from = [13, 2, 3]
+//     look_at = [0, 0, 0]
+//
+//     [textures.checker]
+//     type = "checker"
+//     scale = 0.5
+//     even = [0.2, 0.3, 0.1]
+//     odd = [0.9, 0.9, 0.9]
+//
+//     [materials.ground]
+//     type = "lambertian"
+//     albedo = "checker"
+//
+//     [[objects]]
+//     type = "sphere"
+//     center = [0, -1000, 0]
+//     radius = 1000
+//     material = "ground"
+//
+//     [[prototypes.pillar]]
+//     type = "cylinder"
+//     base = [0, 0, 0]
+//     top = [0, 2, 0]
+//     radius = 0.2
+//     material = "ground"
+//
+//     [[objects]]
+//     type = "instance"
+//     prototype = "pillar"
+//     scale = 0.5
+//     rotate = [0, 0, 10]
+//     translate = [1, 0, 0]
+//
+//     [[lights]]
+//     type = "directional"
+//     direction = [-1, -2, -1]
+//     irradiance = [3, 3, 3]
+//     angular_diameter = 0.5
+pub struct Scene {
+    pub camera: Camera,
+    pub world: HittableList,
+}
+
+#[derive(Debug)]
+pub struct SceneError {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+type Vec3Desc = [f64; 3];
+
+fn to_vec3([x, y, z]: Vec3Desc) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    #[serde(default)]
+    camera: CameraDesc,
+    #[serde(default)]
+    textures: HashMap<String, Spanned<TextureDesc>>,
+    #[serde(default)]
+    materials: HashMap<String, Spanned<MaterialDesc>>,
+    // Groups of objects that are only rendered through `instance` objects, which share
+    // their geometry. Prototypes cannot contain instances.
+    #[serde(default)]
+    prototypes: HashMap<String, Vec<Spanned<ObjectDesc>>>,
+    #[serde(default)]
+    objects: Vec<Spanned<ObjectDesc>>,
+    #[serde(default)]
+    lights: Vec<Spanned<LightDesc>>,
+    fog: Option<Spanned<FogDesc>>,
+}
+
+// Every field is optional and falls back to `Camera::default()`, except `focus_dist`
+// which defaults to the distance between `look_from` and `look_at`.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    aspect_ratio: Option<f64>,
+    image_width: Option<Spanned<u64>>,
+    samples_per_pixel: Option<u64>,
+    max_ray_bounces: Option<u64>,
+    roulette_depth: Option<u64>,
+    vfov: Option<f64>,
+    look_from: Option<Vec3Desc>,
+    look_at: Option<Vec3Desc>,
+    up_direction: Option<Vec3Desc>,
+    defocus_angle: Option<f64>,
+    focus_dist: Option<f64>,
+    // The times `[open, close]` between which the shutter is open. Objects move from
+    // their start at time 0 to their end at time 1, so `[0, 1]` shows the whole motion.
+    shutter: Option<Spanned<[f64; 2]>>,
+    seed: Option<u64>,
+    // Either `"sky"` or a solid color such as `[0, 0, 0]`.
+    background: Option<Spanned<toml::Value>>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+    Solid {
+        color: Vec3Desc,
+    },
+    Checker {
+        scale: f64,
+        even: Vec3Desc,
+        odd: Vec3Desc,
+    },
+    // PNG file, resolved relative to the scene file.
+    Image {
+        path: String,
+    },
+    Noise {
+        scale: f64,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+// A material color: either a constant like `[0.5, 0.5, 0.5]` or the name of a texture.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorDesc {
+    Constant(Vec3Desc),
+    Texture(String),
+}
+
+fn white() -> ColorDesc {
+    ColorDesc::Constant([1., 1., 1.])
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian {
+        albedo: ColorDesc,
+    },
+    Metal {
+        albedo: ColorDesc,
+        #[serde(default)]
+        fuzz: f64,
+    },
+    Dielectric {
+        refractive_index: f64,
+        #[serde(default = "white")]
+        albedo: ColorDesc,
+    },
+    DiffuseLight {
+        emit: ColorDesc,
+    },
+    // Phase functions, for the inside of media.
+    Isotropic {
+        #[serde(default = "white")]
+        albedo: ColorDesc,
+    },
+    HenyeyGreenstein {
+        #[serde(default = "white")]
+        albedo: ColorDesc,
+        g: f64,
+    },
+}
+
+fn default_capped() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDesc {
+    // With an `end_center`, the sphere moves there from `center` between time 0 and 1.
+    Sphere {
+        center: Vec3Desc,
+        end_center: Option<Vec3Desc>,
+        radius: f64,
+        material: String,
+    },
+    Triangle {
+        vertices: [Vec3Desc; 3],
+        normals: Option<[Vec3Desc; 3]>,
+        material: String,
+    },
+    Mesh {
+        positions: Vec<Vec3Desc>,
+        indices: Vec<[usize; 3]>,
+        normals: Option<Vec<Vec3Desc>>,
+        material: String,
+    },
+    // Parallelogram with a corner and the two sides starting from it.
+    Quad {
+        corner: Vec3Desc,
+        u: Vec3Desc,
+        v: Vec3Desc,
+        material: String,
+    },
+    Plane {
+        point: Vec3Desc,
+        normal: Vec3Desc,
+        material: String,
+    },
+    Disk {
+        center: Vec3Desc,
+        normal: Vec3Desc,
+        radius: f64,
+        material: String,
+    },
+    // Axis-aligned box between two opposite corners.
+    Box {
+        corners: [Vec3Desc; 2],
+        material: String,
+    },
+    Cylinder {
+        base: Vec3Desc,
+        top: Vec3Desc,
+        radius: f64,
+        #[serde(default = "default_capped")]
+        capped: bool,
+        material: String,
+    },
+    // A `top_radius` of zero, the default, makes `top` the apex.
+    Cone {
+        base: Vec3Desc,
+        top: Vec3Desc,
+        base_radius: f64,
+        #[serde(default)]
+        top_radius: f64,
+        #[serde(default = "default_capped")]
+        capped: bool,
+        material: String,
+    },
+    Torus {
+        center: Vec3Desc,
+        axis: Vec3Desc,
+        major_radius: f64,
+        minor_radius: f64,
+        material: String,
+    },
+    Capsule {
+        start: Vec3Desc,
+        end: Vec3Desc,
+        radius: f64,
+        material: String,
+    },
+    // A medium of constant density, scattering with the phase function `material`, that
+    // fills the inside of `boundary`. The boundary is an object of its own, which must be
+    // closed, and its material is not used.
+    Medium {
+        boundary: Box<ObjectDesc>,
+        density: f64,
+        material: String,
+    },
+    // Wavefront OBJ file, resolved relative to the scene file. It brings its own materials.
+    Obj {
+        path: String,
+    },
+    // A copy of a prototype, scaled, then rotated by angles in degrees around the x, y and
+    // z axes in that order, then translated. With an `end` placement the instance moves
+    // there between time 0 and 1; what `end` leaves out stays as it is.
+    Instance {
+        prototype: String,
+        scale: Option<ScaleDesc>,
+        rotate: Option<Vec3Desc>,
+        translate: Option<Vec3Desc>,
+        end: Option<PlacementDesc>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlacementDesc {
+    scale: Option<ScaleDesc>,
+    rotate: Option<Vec3Desc>,
+    translate: Option<Vec3Desc>,
+}
+
+// Fog filling the ball of `radius` around `center`, which should hold the whole scene.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FogDesc {
+    density: f64,
+    #[serde(default = "white")]
+    albedo: ColorDesc,
+    // The Henyey-Greenstein `g`; zero scatters the same in all directions.
+    #[serde(default)]
+    g: f64,
+    #[serde(default)]
+    center: Vec3Desc,
+    radius: f64,
+}
+
+// Either one factor for all axes or one per axis.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScaleDesc {
+    Uniform(f64),
+    PerAxis(Vec3Desc),
+}
+
+// Angles are in degrees. Directions are the ones the light travels in.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum LightDesc {
+    Point {
+        position: Vec3Desc,
+        intensity: Vec3Desc,
+    },
+    Spot {
+        position: Vec3Desc,
+        direction: Vec3Desc,
+        intensity: Vec3Desc,
+        cone_angle: f64,
+        #[serde(default)]
+        falloff_angle: f64,
+    },
+    Directional {
+        direction: Vec3Desc,
+        irradiance: Vec3Desc,
+        #[serde(default)]
+        angular_diameter: f64,
+    },
+}
+
+impl CameraDesc {
+    // Errors come with the span of the offending value.
+    fn build(self) -> Result<Camera, (Range<usize>, String)> {
+        let mut cam = Camera::default();
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            cam.aspect_ratio = aspect_ratio;
+        }
+        if let Some(image_width) = self.image_width {
+            if *image_width.get_ref() == 0 {
+                return Err((
+                    image_width.span(),
+                    "`image_width` must be at least 1".to_string(),
+                ));
+            }
+            cam.image_width = *image_width.get_ref();
+        }
+        if let Some(samples_per_pixel) = self.samples_per_pixel {
+            cam.samples_per_pixel = samples_per_pixel;
+        }
+        if let Some(max_ray_bounces) = self.max_ray_bounces {
+            cam.max_ray_bounces = max_ray_bounces;
+        }
+        if let Some(roulette_depth) = self.roulette_depth {
+            cam.roulette_depth = roulette_depth;
+        }
+        if let Some(vfov) = self.vfov {
+            cam.vfov = vfov;
+        }
+        if let Some(look_from) = self.look_from {
+            cam.look_from = to_vec3(look_from);
+        }
+        if let Some(look_at) = self.look_at {
+            cam.look_at = to_vec3(look_at);
+        }
+        if let Some(up_direction) = self.up_direction {
+            cam.up_direction = to_vec3(up_direction);
+        }
+        if let Some(defocus_angle) = self.defocus_angle {
+            cam.defocus_angle = defocus_angle;
+        }
+        if let Some(shutter) = self.shutter {
+            let [open, close] = *shutter.get_ref();
+            if open > close {
+                return Err((
+                    shutter.span(),
+                    "the shutter must open before it closes".to_string(),
+                ));
+            }
+            (cam.shutter_open, cam.shutter_close) = (open, close);
+        }
+        if let Some(seed) = self.seed {
+            cam.seed = seed;
+        }
+        cam.focus_dist = self
+            .focus_dist
+            .unwrap_or_else(|| (cam.look_from - cam.look_at).length());
+        if let Some(background) = self.background {
+            cam.background = parse_background(background.get_ref()).ok_or_else(|| {
+                (
+                    background.span(),
+                    "`background` must be \"sky\" or a color like [0, 0, 0]".to_string(),
+                )
+            })?;
+        }
+        Ok(cam)
+    }
+}
+
+fn parse_background(value: &toml::Value) -> Option<Background> {
+    match value {
+        toml::Value::String(name) if name == "sky" => Some(Background::Sky),
+        toml::Value::Array(components) => {
+            let components: Vec<f64> = components
+                .iter()
+                .map(|c| c.as_float().or_else(|| c.as_integer().map(|i| i as f64)))
+                .collect::<Option<_>>()?;
+            match components[..] {
+                [r, g, b] => Some(Background::Solid(Color::new(r, g, b))),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+struct SceneBuilder<'a> {
+    path: &'a Path,
+    source: &'a str,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    prototypes: HashMap<String, Arc<dyn Hittable>>,
+}
+
+impl SceneBuilder<'_> {
+    fn error_at(&self, span: &Range<usize>, message: String) -> SceneError {
+        SceneError {
+            path: self.path.to_path_buf(),
+            line: Some(line_of(self.source, span.start)),
+            message,
+        }
+    }
+
+    fn relative_path(&self, path: &str) -> PathBuf {
+        self.path
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(path)
+    }
+
+    fn build_texture(&self, texture: Spanned<TextureDesc>) -> Result<Arc<dyn Texture>, SceneError> {
+        let span = texture.span();
+        Ok(match texture.into_inner() {
+            TextureDesc::Solid { color } => Arc::new(SolidColor::new(to_vec3(color))),
+            TextureDesc::Checker { scale, even, odd } => Arc::new(CheckerTexture::from_colors(
+                scale,
+                to_vec3(even),
+                to_vec3(odd),
+            )),
+            TextureDesc::Image { path } => {
+                let image_path = self.relative_path(&path);
+                Arc::new(ImageTexture::load(&image_path).map_err(|error| {
+                    self.error_at(
+                        &span,
+                        format!("cannot load image {}: {}", image_path.display(), error),
+                    )
+                })?)
+            }
+            TextureDesc::Noise { scale, seed } => Arc::new(NoiseTexture::with_seed(scale, seed)),
+        })
+    }
+
+    fn texture(
+        &self,
+        color: ColorDesc,
+        span: &Range<usize>,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        match color {
+            ColorDesc::Constant(color) => Ok(Arc::new(SolidColor::new(to_vec3(color)))),
+            ColorDesc::Texture(name) => self
+                .textures
+                .get(&name)
+                .map(Arc::clone)
+                .ok_or_else(|| self.error_at(span, format!("unknown texture `{}`", name))),
+        }
+    }
+
+    fn build_material(
+        &self,
+        material: Spanned<MaterialDesc>,
+    ) -> Result<Arc<dyn Material>, SceneError> {
+        let span = material.span();
+        Ok(match material.into_inner() {
+            MaterialDesc::Lambertian { albedo } => {
+                Arc::new(Lambertian::from_texture(self.texture(albedo, &span)?))
+            }
+            MaterialDesc::Metal { albedo, fuzz } => {
+                Arc::new(Metal::from_texture(self.texture(albedo, &span)?, fuzz))
+            }
+            MaterialDesc::Dielectric {
+                refractive_index,
+                albedo,
+            } => Arc::new(Dielectric::from_texture(
+                self.texture(albedo, &span)?,
+                refractive_index,
+            )),
+            MaterialDesc::DiffuseLight { emit } => {
+                Arc::new(DiffuseLight::from_texture(self.texture(emit, &span)?))
+            }
+            MaterialDesc::Isotropic { albedo } => {
+                Arc::new(Isotropic::from_texture(self.texture(albedo, &span)?))
+            }
+            MaterialDesc::HenyeyGreenstein { albedo, g } => {
+                self.henyey_greenstein(albedo, g, &span)?
+            }
+        })
+    }
+
+    fn henyey_greenstein(
+        &self,
+        albedo: ColorDesc,
+        g: f64,
+        span: &Range<usize>,
+    ) -> Result<Arc<dyn Material>, SceneError> {
+        if g <= -1. || g >= 1. {
+            return Err(self.error_at(span, format!("`g` must be between -1 and 1, got {}", g)));
+        }
+        Ok(Arc::new(HenyeyGreenstein::from_texture(
+            self.texture(albedo, span)?,
+            g,
+        )))
+    }
+
+    fn build_light(&self, light: Spanned<LightDesc>) -> Result<Arc<dyn Light>, SceneError> {
+        let span = light.span();
+        let direction = |direction: Vec3Desc| {
+            let direction = to_vec3(direction);
+            if direction.near_zero() {
+                Err(self.error_at(&span, "`direction` must not be zero".to_string()))
+            } else {
+                Ok(direction)
+            }
+        };
+        Ok(match light.into_inner() {
+            LightDesc::Point {
+                position,
+                intensity,
+            } => Arc::new(PointLight::new(to_vec3(position), to_vec3(intensity))),
+            LightDesc::Spot {
+                position,
+                direction: spot_direction,
+                intensity,
+                cone_angle,
+                falloff_angle,
+            } => {
+                if !(cone_angle > 0. && cone_angle <= 180.) {
+                    return Err(self.error_at(
+                        &span,
+                        format!("`cone_angle` must be in (0, 180], got {}", cone_angle),
+                    ));
+                }
+                if !(0. ..=cone_angle).contains(&falloff_angle) {
+                    return Err(self.error_at(
+                        &span,
+                        format!(
+                            "`falloff_angle` must be between 0 and `cone_angle`, got {}",
+                            falloff_angle
+                        ),
+                    ));
+                }
+                Arc::new(SpotLight::new(
+                    to_vec3(position),
+                    direction(spot_direction)?,
+                    to_vec3(intensity),
+                    cone_angle,
+                    falloff_angle,
+                ))
+            }
+            LightDesc::Directional {
+                direction: light_direction,
+                irradiance,
+                angular_diameter,
+            } => {
+                if !(0. ..180.).contains(&angular_diameter) {
+                    return Err(self.error_at(
+                        &span,
+                        format!(
+                            "`angular_diameter` must be in [0, 180), got {}",
+                            angular_diameter
+                        ),
+                    ));
+                }
+                Arc::new(DirectionalLight::new(
+                    direction(light_direction)?,
+                    to_vec3(irradiance),
+                    angular_diameter,
+                ))
+            }
+        })
+    }
+
+    fn material(&self, name: &str, span: &Range<usize>) -> Result<Arc<dyn Material>, SceneError> {
+        self.materials.get(name).map(Arc::clone).ok_or_else(|| {
+            self.error_at(span, format!("unknown material `{}` in `material`", name))
+        })
+    }
+
+    fn normal(&self, normal: Vec3Desc, span: &Range<usize>) -> Result<Vec3, SceneError> {
+        let normal = to_vec3(normal);
+        if normal.near_zero() {
+            Err(self.error_at(span, "`normal` must not be zero".to_string()))
+        } else {
+            Ok(normal)
+        }
+    }
+
+    fn prototype(&self, name: &str, span: &Range<usize>) -> Result<Arc<dyn Hittable>, SceneError> {
+        self.prototypes.get(name).map(Arc::clone).ok_or_else(|| {
+            self.error_at(span, format!("unknown prototype `{}` in `prototype`", name))
+        })
+    }
+
+    fn check_positive(
+        &self,
+        name: &str,
+        value: f64,
+        span: &Range<usize>,
+    ) -> Result<(), SceneError> {
+        if value > 0. {
+            Ok(())
+        } else {
+            Err(self.error_at(span, format!("`{}` must be positive, got {}", name, value)))
+        }
+    }
+
+    // The two ends of the axis of a cylinder or cone, which must not coincide.
+    fn axis_ends(
+        &self,
+        base: Vec3Desc,
+        top: Vec3Desc,
+        span: &Range<usize>,
+    ) -> Result<(Point3, Point3), SceneError> {
+        let (base, top) = (to_vec3(base), to_vec3(top));
+        if (top - base).near_zero() {
+            Err(self.error_at(
+                span,
+                "`base` and `top` must be different points".to_string(),
+            ))
+        } else {
+            Ok((base, top))
+        }
+    }
+
+    fn add_object(
+        &self,
+        world: &mut HittableList,
+        object: Spanned<ObjectDesc>,
+    ) -> Result<(), SceneError> {
+        let span = object.span();
+        self.add_object_at(world, object.into_inner(), span)
+    }
+
+    // Errors found here point at `span`, the `[[objects]]` entry the object comes from.
+    fn add_object_at(
+        &self,
+        world: &mut HittableList,
+        object: ObjectDesc,
+        span: Range<usize>,
+    ) -> Result<(), SceneError> {
+        match object {
+            ObjectDesc::Sphere {
+                center,
+                end_center,
+                radius,
+                material,
+            } => world.add(Arc::new(Sphere::moving(
+                to_vec3(center),
+                to_vec3(end_center.unwrap_or(center)),
+                radius,
+                self.material(&material, &span)?,
+            ))),
+            ObjectDesc::Triangle {
+                vertices,
+                normals,
+                material,
+            } => {
+                let vertices = vertices.map(to_vec3);
+                let material = self.material(&material, &span)?;
+                world.add(Arc::new(match normals {
+                    Some(normals) => {
+                        Triangle::with_normals(vertices, normals.map(to_vec3), material)
+                    }
+                    None => {
+                        let [a, b, c] = vertices;
+                        Triangle::new(a, b, c, material)
+                    }
+                }))
+            }
+            ObjectDesc::Mesh {
+                positions,
+                indices,
+                normals,
+                material,
+            } => {
+                let material = self.material(&material, &span)?;
+                if let Some(index) = indices.iter().flatten().find(|&&i| i >= positions.len()) {
+                    return Err(self.error_at(
+                        &span,
+                        format!(
+                            "index {} in `indices` is out of range for {} positions",
+                            index,
+                            positions.len()
+                        ),
+                    ));
+                }
+                let positions: Vec<Point3> = positions.into_iter().map(to_vec3).collect();
+                world.add(Arc::new(match normals {
+                    Some(normals) => {
+                        if normals.len() != positions.len() {
+                            return Err(self.error_at(
+                                &span,
+                                format!(
+                                    "`normals` needs one normal per position, got {} for {} positions",
+                                    normals.len(),
+                                    positions.len()
+                                ),
+                            ));
+                        }
+                        let normals = normals.into_iter().map(to_vec3).collect();
+                        TriangleMesh::with_normals(positions, normals, indices, material)
+                    }
+                    None => TriangleMesh::new(positions, indices, material),
+                }))
+            }
+            ObjectDesc::Quad {
+                corner,
+                u,
+                v,
+                material,
+            } => {
+                let (u, v) = (to_vec3(u), to_vec3(v));
+                if u.cross(&v).near_zero() {
+                    return Err(self.error_at(
+                        &span,
+                        "`u` and `v` must be nonzero and not parallel".to_string(),
+                    ));
+                }
+                world.add(Arc::new(Quad::new(
+                    to_vec3(corner),
+                    u,
+                    v,
+                    self.material(&material, &span)?,
+                )))
+            }
+            ObjectDesc::Plane {
+                point,
+                normal,
+                material,
+            } => world.add(Arc::new(Plane::new(
+                to_vec3(point),
+                self.normal(normal, &span)?,
+                self.material(&material, &span)?,
+            ))),
+            ObjectDesc::Disk {
+                center,
+                normal,
+                radius,
+                material,
+            } => {
+                if radius <= 0. {
+                    return Err(
+                        self.error_at(&span, format!("`radius` must be positive, got {}", radius))
+                    );
+                }
+                world.add(Arc::new(Disk::new(
+                    to_vec3(center),
+                    self.normal(normal, &span)?,
+                    radius,
+                    self.material(&material, &span)?,
+                )))
+            }
+            ObjectDesc::Box { corners, material } => {
+                let [a, b] = corners.map(to_vec3);
+                world.add(Arc::new(make_box(a, b, self.material(&material, &span)?)))
+            }
+            ObjectDesc::Cylinder {
+                base,
+                top,
+                radius,
+                capped,
+                material,
+            } => {
+                let (base, top) = self.axis_ends(base, top, &span)?;
+                self.check_positive("radius", radius, &span)?;
+                world.add(Arc::new(Cylinder::new(
+                    base,
+                    top,
+                    radius,
+                    capped,
+                    self.material(&material, &span)?,
+                )))
+            }
+            ObjectDesc::Cone {
+                base,
+                top,
+                base_radius,
+                top_radius,
+                capped,
+                material,
+            } => {
+                let (base, top) = self.axis_ends(base, top, &span)?;
+                if base_radius < 0. || top_radius < 0. || base_radius + top_radius <= 0. {
+                    return Err(self.error_at(
+                        &span,
+                        format!(
+                            "the radii of a cone must not be negative or both zero, got {} and {}",
+                            base_radius, top_radius
+                        ),
+                    ));
+                }
+                world.add(Arc::new(Cone::new(
+                    base,
+                    top,
+                    base_radius,
+                    top_radius,
+                    capped,
+                    self.material(&material, &span)?,
+                )))
+            }
+            ObjectDesc::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+                material,
+            } => {
+                let axis = to_vec3(axis);
+                if axis.near_zero() {
+                    return Err(self.error_at(&span, "`axis` must not be zero".to_string()));
+                }
+                self.check_positive("major_radius", major_radius, &span)?;
+                self.check_positive("minor_radius", minor_radius, &span)?;
+                world.add(Arc::new(Torus::new(
+                    to_vec3(center),
+                    axis,
+                    major_radius,
+                    minor_radius,
+                    self.material(&material, &span)?,
+                )))
+            }
+            ObjectDesc::Capsule {
+                start,
+                end,
+                radius,
+                material,
+            } => {
+                self.check_positive("radius", radius, &span)?;
+                world.add(Arc::new(Capsule::new(
+                    to_vec3(start),
+                    to_vec3(end),
+                    radius,
+                    self.material(&material, &span)?,
+                )))
+            }
+            ObjectDesc::Medium {
+                boundary,
+                density,
+                material,
+            } => {
+                self.check_positive("density", density, &span)?;
+                let mut shape = HittableList::new_empty();
+                self.add_object_at(&mut shape, *boundary, span.clone())?;
+                // A single object is used as it is, a BVH is only worth it for more.
+                let boundary = match shape.len() {
+                    1 => shape.into_objects().remove(0),
+                    _ => Arc::new(BvhNode::new(shape)),
+                };
+                world.add(Arc::new(ConstantMedium::new(
+                    boundary,
+                    density,
+                    self.material(&material, &span)?,
+                )))
+            }
+            ObjectDesc::Obj { path } => {
+                let obj_path = self.relative_path(&path);
+                let meshes = load_obj(&obj_path).map_err(|error| {
+                    self.error_at(&span, format!("cannot load OBJ file: {}", error))
+                })?;
+                // Added mesh by mesh, so that a BVH over the world can separate them.
+                for mesh in meshes.into_objects() {
+                    world.add(mesh);
+                }
+            }
+            ObjectDesc::Instance {
+                prototype,
+                scale,
+                rotate,
+                translate,
+                end,
+            } => {
+                let prototype = self.prototype(&prototype, &span)?;
+                let start = PlacementDesc {
+                    scale,
+                    rotate,
+                    translate,
+                }
+                .build(&Placement::default());
+                let end = end.map(|end| end.build(&start));
+                // The object must not flatten to nothing at any time, so the scale factors
+                // are nonzero and keep their signs.
+                let first = start.scale;
+                let flattens = |scale: Vec3| {
+                    scale.x * first.x <= 0. || scale.y * first.y <= 0. || scale.z * first.z <= 0.
+                };
+                if flattens(first) || end.is_some_and(|end| flattens(end.scale)) {
+                    return Err(
+                        self.error_at(&span, "`scale` must not be zero or change sign".to_string())
+                    );
+                }
+                world.add(Arc::new(match end {
+                    Some(end) => Instance::moving(prototype, start, end),
+                    None => Instance::new(prototype, start.transform()),
+                }))
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PlacementDesc {
+    // Parts that are not given are taken from `base`.
+    fn build(self, base: &Placement) -> Placement {
+        Placement {
+            scale: match self.scale {
+                Some(ScaleDesc::Uniform(factor)) => Vec3::new(factor, factor, factor),
+                Some(ScaleDesc::PerAxis(factors)) => to_vec3(factors),
+                None => base.scale,
+            },
+            rotation: self.rotate.map_or(base.rotation, to_vec3),
+            translation: self.translate.map_or(base.translation, to_vec3),
+        }
+    }
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+pub fn load_scene(path: impl AsRef<Path>) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|error| SceneError {
+        path: path.to_path_buf(),
+        line: None,
+        message: error.to_string(),
+    })?;
+    parse_scene(&source, path)
+}
+
+// Parses scene source text. `path` is used for error messages and to resolve files that
+// the scene refers to.
+pub fn parse_scene(source: &str, path: &Path) -> Result<Scene, SceneError> {
+    let desc: SceneDesc = toml::from_str(source).map_err(|error| SceneError {
+        path: path.to_path_buf(),
+        line: error.span().map(|span| line_of(source, span.start)),
+        message: error.message().to_string(),
+    })?;
+
+    let mut builder = SceneBuilder {
+        path,
+        source,
+        textures: HashMap::new(),
+        materials: HashMap::new(),
+        prototypes: HashMap::new(),
+    };
+    for (name, texture) in desc.textures {
+        let texture = builder.build_texture(texture)?;
+        builder.textures.insert(name, texture);
+    }
+    for (name, material) in desc.materials {
+        let material = builder.build_material(material)?;
+        builder.materials.insert(name, material);
+    }
+
+    // Built before being made visible, so that prototypes never see each other.
+    let mut prototypes = HashMap::new();
+    for (name, objects) in desc.prototypes {
+        let mut group = HittableList::new_empty();
+        for object in objects {
+            builder.add_object(&mut group, object)?;
+        }
+        prototypes.insert(name, Arc::new(BvhNode::new(group)) as Arc<dyn Hittable>);
+    }
+    builder.prototypes = prototypes;
+
+    let mut world = HittableList::new_empty();
+    for object in desc.objects {
+        builder.add_object(&mut world, object)?;
+    }
+
+    if let Some(fog) = desc.fog {
+        let span = fog.span();
+        let fog = fog.into_inner();
+        builder.check_positive("density", fog.density, &span)?;
+        builder.check_positive("radius", fog.radius, &span)?;
+        let phase_function = builder.henyey_greenstein(fog.albedo, fog.g, &span)?;
+        world.add(Arc::new(ConstantMedium::fog(
+            to_vec3(fog.center),
+            fog.radius,
+            fog.density,
+            phase_function,
+        )));
+    }
+
+    let mut camera = desc
+        .camera
+        .build()
+        .map_err(|(span, message)| builder.error_at(&span, message))?;
+    // Analytic lights are kept with the camera, like the background.
+    for light in desc.lights {
+        camera.lights.push(builder.build_light(light)?);
+    }
+
+    Ok(Scene { camera, world })
+}
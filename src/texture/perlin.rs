@@ -0,0 +1,116 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+
+use crate::my_math::prelude::*;
+
+// Ken Perlin's gradient noise: random unit vectors on a lattice, blended with a smooth
+// Hermite curve so that the result has no blocky artifacts.
+pub struct Perlin {
+    random_vectors: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    const POINT_COUNT: usize = 256;
+
+    pub fn new() -> Self {
+        Perlin::with_seed(0)
+    }
+
+    // The lattice is generated from `seed`, so the same seed always gives the same noise.
+    pub fn with_seed(seed: u64) -> Self {
+        let mut rng = Pcg64Mcg::seed_from_u64(seed);
+        Perlin {
+            random_vectors: (0..Self::POINT_COUNT)
+                .map(|_| {
+                    Vec3::new(
+                        rng.gen_range(-1. ..1.),
+                        rng.gen_range(-1. ..1.),
+                        rng.gen_range(-1. ..1.),
+                    )
+                    .normalized()
+                })
+                .collect(),
+            perm_x: Self::generate_permutation(&mut rng),
+            perm_y: Self::generate_permutation(&mut rng),
+            perm_z: Self::generate_permutation(&mut rng),
+        }
+    }
+
+    // Noise in the range [-1, 1].
+    pub fn noise(&self, point: &Point3) -> f64 {
+        let u = point.x - point.x.floor();
+        let v = point.y - point.y.floor();
+        let w = point.z - point.z.floor();
+
+        let i = point.x.floor() as i64;
+        let j = point.y.floor() as i64;
+        let k = point.z.floor() as i64;
+
+        let mask = Self::POINT_COUNT as i64 - 1;
+        let mut corners = [[[Vec3::new(0., 0., 0.); 2]; 2]; 2];
+        for (di, plane) in corners.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let index = self.perm_x[((i + di as i64) & mask) as usize]
+                        ^ self.perm_y[((j + dj as i64) & mask) as usize]
+                        ^ self.perm_z[((k + dk as i64) & mask) as usize];
+                    *corner = self.random_vectors[index];
+                }
+            }
+        }
+
+        Self::interpolate(&corners, u, v, w)
+    }
+
+    // Sum of `depth` octaves of noise, each with double the frequency and half the weight.
+    pub fn turbulence(&self, point: &Point3, depth: u32) -> f64 {
+        let mut accumulated = 0.;
+        let mut point = *point;
+        let mut weight = 1.;
+        for _ in 0..depth {
+            accumulated += weight * self.noise(&point);
+            weight *= 0.5;
+            point = point * 2.;
+        }
+        accumulated.abs()
+    }
+
+    fn generate_permutation(rng: &mut impl Rng) -> Vec<usize> {
+        let mut permutation: Vec<usize> = (0..Self::POINT_COUNT).collect();
+        // Fisher-Yates shuffle.
+        for i in (1..Self::POINT_COUNT).rev() {
+            permutation.swap(i, rng.gen_range(0..=i));
+        }
+        permutation
+    }
+
+    fn interpolate(corners: &[[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+        let uu = u * u * (3. - 2. * u);
+        let vv = v * v * (3. - 2. * v);
+        let ww = w * w * (3. - 2. * w);
+
+        let mut accumulated = 0.;
+        for (i, plane) in corners.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, corner) in row.iter().enumerate() {
+                    let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                    let weight = Vec3::new(u - fi, v - fj, w - fk);
+                    accumulated += (fi * uu + (1. - fi) * (1. - uu))
+                        * (fj * vv + (1. - fj) * (1. - vv))
+                        * (fk * ww + (1. - fk) * (1. - ww))
+                        * corner.dot(&weight);
+                }
+            }
+        }
+        accumulated
+    }
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Perlin::new()
+    }
+}
@@ -0,0 +1,156 @@
+use std::{fs::File, io, path::Path, sync::Arc};
+
+use super::perlin::Perlin;
+use crate::{color::Color, my_math::prelude::*};
+
+// A color that varies over a surface, looked up by the surface (u, v) coordinates
+// and/or by the hit point itself.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, point: &Point3) -> Color;
+}
+
+pub struct SolidColor {
+    albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        SolidColor { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _point: &Point3) -> Color {
+        self.albedo
+    }
+}
+
+// A 3D checker pattern of cubes with side `scale`, alternating between two textures.
+// Being solid, it does not depend on how the surface is parameterized.
+pub struct CheckerTexture {
+    inv_scale: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        CheckerTexture {
+            inv_scale: 1. / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(scale: f64, even: Color, odd: Color) -> Self {
+        CheckerTexture::new(
+            scale,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, point: &Point3) -> Color {
+        let x = (self.inv_scale * point.x).floor() as i64;
+        let y = (self.inv_scale * point.y).floor() as i64;
+        let z = (self.inv_scale * point.z).floor() as i64;
+
+        if (x + y + z) % 2 == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
+
+// Texture read from an image file, mapped onto the surface with its (u, v) coordinates.
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    // Linear colors, top row first.
+    pixels: Vec<Color>,
+}
+
+impl ImageTexture {
+    // Loads a PNG image. Its colors are converted back from gamma 2 to linear space,
+    // the inverse of what the renderer does when saving.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut decoder = png::Decoder::new(File::open(path)?);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+
+        let channels = info.color_type.samples();
+        let to_linear = |byte: u8| (byte as f64 / 255.).powi(2);
+        let pixels = buffer[..info.buffer_size()]
+            .chunks_exact(channels)
+            .map(|pixel| match pixel {
+                // Grayscale, with or without alpha.
+                [l] | [l, _] => Color::new(to_linear(*l), to_linear(*l), to_linear(*l)),
+                // RGB, with or without alpha.
+                [r, g, b, ..] => Color::new(to_linear(*r), to_linear(*g), to_linear(*b)),
+                [] => unreachable!("png pixels have at least one channel"),
+            })
+            .collect();
+
+        Ok(ImageTexture {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _point: &Point3) -> Color {
+        if self.pixels.is_empty() {
+            // Debugging aid: a missing texture shows up as solid cyan.
+            return Color::new(0., 1., 1.);
+        }
+
+        let unit = Interval::new(0., 1.);
+        let u = unit.clamp(u);
+        // Image rows go down while v goes up.
+        let v = 1. - unit.clamp(v);
+
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[j * self.width + i]
+    }
+}
+
+// Marble-like procedural texture: sine stripes along z, disturbed by Perlin turbulence.
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f64,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64) -> Self {
+        NoiseTexture::with_seed(scale, 0)
+    }
+
+    pub fn with_seed(scale: f64, seed: u64) -> Self {
+        NoiseTexture {
+            noise: Perlin::with_seed(seed),
+            scale,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, point: &Point3) -> Color {
+        let phase = self.scale * point.z + 10. * self.noise.turbulence(point, 7);
+        Color::new(0.5, 0.5, 0.5) * (1. + phase.sin())
+    }
+}